@@ -16,6 +16,14 @@ impl Aabb {
         Self { min, max }
     }
 
+    // box spanned by two opposite corners given in any order
+    pub fn from_points(a: Vec3, b: Vec3) -> Self {
+        Aabb::new(
+            vec3(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            vec3(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        )
+    }
+
     pub fn join(&self, other: &Self) -> Self {
         Aabb::new(
             vec3(
@@ -35,22 +43,48 @@ impl Aabb {
         *self = self.join(other)
     }
 
-    pub fn longest_axis(&self) -> usize {
-        [
+    // grows flat axes a little so zero-thickness boxes can still be hit. the pad
+    // scales with the coordinates, a fixed one would round away far from the origin
+    pub fn padded(&self) -> Self {
+        let pad = |min: f32, max: f32| {
+            let delta = (min.abs().max(max.abs()) * f32::EPSILON * 8.0).max(1e-4);
+            if max - min < delta {
+                (min - delta / 2.0, max + delta / 2.0)
+            } else {
+                (min, max)
+            }
+        };
+
+        let (min_x, max_x) = pad(self.min.x, self.max.x);
+        let (min_y, max_y) = pad(self.min.y, self.max.y);
+        let (min_z, max_z) = pad(self.min.z, self.max.z);
+
+        Aabb::new(vec3(min_x, min_y, min_z), vec3(max_x, max_y, max_z))
+    }
+
+    // empty boxes and boxes with nan bounds have no meaningful axis
+    pub fn longest_axis(&self) -> Option<usize> {
+        let extents = [
             self.max.x - self.min.x,
             self.max.y - self.min.y,
             self.max.z - self.min.z,
-        ]
-        .iter()
-        .enumerate()
-        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-        .map(|(index, _)| index)
-        .unwrap_or(0)
+        ];
+
+        if extents.iter().any(|e| e.is_nan() || *e < 0.0) {
+            return None;
+        }
+
+        extents
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
     }
 
-    pub fn hit(&self, ray: &Ray, mut ray_tmin: f32, mut ray_tmax: f32) -> bool {
+    // slab test with the reciprocal ray direction precomputed by the caller
+    pub fn hit(&self, ray: &Ray, inv_dir: Vec3, mut ray_tmin: f32, mut ray_tmax: f32) -> bool {
         for axis in 0..3 {
-            let inv_d = 1.0 / ray.direction[axis];
+            let inv_d = inv_dir[axis];
 
             let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
             let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
@@ -62,7 +96,8 @@ impl Aabb {
             ray_tmin = t0.max(ray_tmin);
             ray_tmax = t1.min(ray_tmax);
 
-            if ray_tmax <= ray_tmin {
+            // the box is closed, a ray whose entry and exit coincide still touches it
+            if ray_tmax < ray_tmin {
                 return false;
             }
         }
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use crate::math::{vec3, Ray, Vec3, PI};
+
+pub enum Background {
+    Solid(Vec3),
+    Gradient { bottom: Vec3, top: Vec3 },
+    Environment(EnvironmentMap),
+}
+
+impl Background {
+    pub fn sky() -> Self {
+        Background::Gradient {
+            bottom: Vec3::ONES,
+            top: vec3(0.5, 0.7, 1.0),
+        }
+    }
+
+    pub fn value(&self, ray: &Ray) -> Vec3 {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = ray.direction.normalize();
+                let a = (unit_direction.y + 1.0) * 0.5;
+
+                *bottom * (1.0 - a) + *top * a
+            }
+            Background::Environment(map) => map.value(ray.direction.normalize()),
+        }
+    }
+}
+
+// equirectangular (latitude-longitude) map, stored as linear radiance
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl EnvironmentMap {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.into_rgb32f();
+        let (width, height) = image.dimensions();
+
+        let pixels = image.pixels().map(|p| vec3(p[0], p[1], p[2])).collect();
+
+        Ok(Self {
+            width: width as usize,
+            height: height as usize,
+            pixels,
+        })
+    }
+
+    fn texel(&self, x: usize, y: usize) -> Vec3 {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn value(&self, direction: Vec3) -> Vec3 {
+        // +y is up and the center of the map looks down -z
+        let u = direction.x.atan2(-direction.z) / (2.0 * PI) + 0.5;
+        let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+
+        // longitude wraps around the seam, latitude clamps at the poles
+        let x0 = (x0 as isize).rem_euclid(self.width as isize) as usize;
+        let x1 = (x0 + 1) % self.width;
+        let y0 = y0 as usize;
+        let y1 = (y0 + 1).min(self.height - 1);
+
+        let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x1, y0) * tx;
+        let bottom = self.texel(x0, y1) * (1.0 - tx) + self.texel(x1, y1) * tx;
+
+        top * (1.0 - ty) + bottom * ty
+    }
+}
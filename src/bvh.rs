@@ -1,81 +1,414 @@
+use std::fmt;
+
 use crate::{
     aabb::Aabb,
     hittable::{HitRecord, Hittable},
 };
 
-use crate::math::Ray;
+use crate::math::{vec3, Ray, Vec3};
+use crate::sampler::Sampler;
+
+// relative cost of stepping through an interior node compared to intersecting one primitive
+const TRAVERSAL_COST: f32 = 0.125;
 
-pub enum BVHNode {
-    Node {
-        left: Box<dyn Hittable>,
-        right: Box<dyn Hittable>,
+// deepest tree the traversal stack can handle, deeper subtrees are collapsed into leaves
+const MAX_DEPTH: usize = 64;
+
+// tree as produced by the builder, flattened into a `BVHNode` afterwards
+enum BuildNode {
+    Interior {
+        left: Box<BuildNode>,
+        right: Box<BuildNode>,
+        axis: usize,
         aabb: Aabb,
     },
-    Leaf(Box<dyn Hittable>),
+    Leaf {
+        hittables: Vec<Box<dyn Hittable>>,
+        aabb: Aabb,
+    },
+}
+
+struct LinearNode {
+    aabb: Aabb,
+    // first primitive of a leaf, or the second child of an interior node whose
+    // first child directly follows it
+    offset: usize,
+    // zero for interior nodes
+    count: usize,
+    axis: usize,
+}
+
+// bvh stored as a depth-first array of nodes and traversed without recursion
+pub struct BVHNode {
+    nodes: Vec<LinearNode>,
+    primitives: Vec<Box<dyn Hittable>>,
 }
 
 impl BVHNode {
     pub fn new(hittables: &mut Vec<Box<dyn Hittable>>) -> Self {
-        match hittables.len() {
-            0 => panic!("cannot construct BVH from an empty hittable list"),
-            1 => BVHNode::Leaf(hittables.pop().unwrap()),
-            2 => {
-                let left = hittables.remove(0);
-                let right = hittables.remove(0);
-                let aabb = left.aabb().join(&right.aabb());
-
-                BVHNode::Node { left, right, aabb }
+        BVHBuilder::new().build(hittables)
+    }
+
+    fn flatten(&mut self, node: BuildNode) -> usize {
+        let index = self.nodes.len();
+
+        match node {
+            BuildNode::Leaf { hittables, aabb } => {
+                self.nodes.push(LinearNode {
+                    aabb,
+                    offset: self.primitives.len(),
+                    count: hittables.len(),
+                    axis: 0,
+                });
+                self.primitives.extend(hittables);
             }
-            _ => {
-                let aabb = hittables
-                    .iter()
-                    .fold(Aabb::EMPTY, |acc, h| acc.join(&h.aabb()));
-                let axis = aabb.longest_axis();
+            BuildNode::Interior {
+                left,
+                right,
+                axis,
+                aabb,
+            } => {
+                self.nodes.push(LinearNode {
+                    aabb,
+                    offset: 0,
+                    count: 0,
+                    axis,
+                });
+                self.flatten(*left);
+                self.nodes[index].offset = self.flatten(*right);
+            }
+        }
+
+        index
+    }
+
+    pub fn stats(&self) -> BVHStats {
+        let mut stats = BVHStats::default();
+        if self.nodes.is_empty() {
+            return stats;
+        }
+
+        stats.sah_cost = self.collect_stats(0, &mut stats, 1);
+        stats
+    }
 
-                hittables
-                    .sort_by(|a, b| a.aabb().min[axis].partial_cmp(&b.aabb().min[axis]).unwrap());
+    // returns the sah cost of this subtree relative to its own surface area
+    fn collect_stats(&self, index: usize, stats: &mut BVHStats, depth: usize) -> f32 {
+        let node = &self.nodes[index];
 
-                let mut right_hittables = hittables.split_off(hittables.len() / 2);
+        stats.nodes += 1;
+        stats.depth = stats.depth.max(depth);
 
-                let left = BVHNode::new(hittables);
-                let right = BVHNode::new(&mut right_hittables);
+        if node.count > 0 {
+            stats.leaves += 1;
+            stats.primitives += node.count;
+            return node.count as f32;
+        }
+
+        let (left, right) = (index + 1, node.offset);
+        let left_cost = self.collect_stats(left, stats, depth + 1);
+        let right_cost = self.collect_stats(right, stats, depth + 1);
 
-                BVHNode::Node {
-                    left: Box::new(left),
-                    right: Box::new(right),
+        let area = surface_area(&node.aabb);
+        if area.is_finite() && area > 0.0 {
+            TRAVERSAL_COST
+                + (surface_area(&self.nodes[left].aabb) * left_cost
+                    + surface_area(&self.nodes[right].aabb) * right_cost)
+                    / area
+        } else {
+            TRAVERSAL_COST + left_cost + right_cost
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BVHStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    pub primitives: usize,
+    pub depth: usize,
+    pub sah_cost: f32,
+}
+
+impl fmt::Display for BVHStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes ({} leaves, {} primitives), depth {}, sah cost {:.2}",
+            self.nodes, self.leaves, self.primitives, self.depth, self.sah_cost
+        )
+    }
+}
+
+// binned surface area heuristic builder
+pub struct BVHBuilder {
+    max_leaf_size: usize,
+    bins: usize,
+}
+
+impl Default for BVHBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Primitive {
+    hittable: Box<dyn Hittable>,
+    aabb: Aabb,
+    centroid: Vec3,
+}
+
+impl BVHBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_leaf_size: 4,
+            bins: 16,
+        }
+    }
+
+    pub fn max_leaf_size(mut self, max_leaf_size: usize) -> Self {
+        self.max_leaf_size = max_leaf_size.max(1);
+        self
+    }
+
+    pub fn bins(mut self, bins: usize) -> Self {
+        self.bins = bins.max(2);
+        self
+    }
+
+    pub fn build(&self, hittables: &mut Vec<Box<dyn Hittable>>) -> BVHNode {
+        // bounds are computed once up front instead of on every comparison
+        let primitives = hittables
+            .drain(..)
+            .map(|hittable| {
+                let aabb = hittable.aabb();
+                Primitive {
+                    hittable,
                     aabb,
+                    centroid: centroid(&aabb),
                 }
+            })
+            .collect::<Vec<_>>();
+
+        let mut bvh = BVHNode {
+            nodes: Vec::new(),
+            primitives: Vec::new(),
+        };
+
+        // an empty scene gives an empty tree that nothing can hit
+        if !primitives.is_empty() {
+            let root = self.build_node(primitives, 1);
+            bvh.flatten(root);
+        }
+
+        bvh
+    }
+
+    fn build_node(&self, primitives: Vec<Primitive>, depth: usize) -> BuildNode {
+        let aabb = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| acc.join(&p.aabb));
+
+        if primitives.len() == 1 || depth >= MAX_DEPTH {
+            return leaf(primitives, aabb);
+        }
+
+        let centroid_bounds = primitives.iter().fold(Aabb::EMPTY, |acc, p| {
+            acc.join(&Aabb::new(p.centroid, p.centroid))
+        });
+        // `longest_axis` is none for empty or nan bounds. `centroid` maps unbounded and nan
+        // axes to the origin, so this only guards against that mapping ever changing
+        let Some(axis) = centroid_bounds.longest_axis() else {
+            return leaf(primitives, aabb);
+        };
+        let (cmin, cmax) = (centroid_bounds.min[axis], centroid_bounds.max[axis]);
+
+        // all centroids coincide, so no plane can separate them
+        if cmax - cmin <= 0.0 {
+            if primitives.len() <= self.max_leaf_size {
+                return leaf(primitives, aabb);
             }
+
+            return self.split_median(primitives, axis, aabb, depth);
+        }
+
+        let bin_of = |p: &Primitive| {
+            let b = ((p.centroid[axis] - cmin) / (cmax - cmin) * self.bins as f32) as usize;
+            b.min(self.bins - 1)
+        };
+
+        let mut bin_counts = vec![0usize; self.bins];
+        let mut bin_bounds = vec![Aabb::EMPTY; self.bins];
+        for p in &primitives {
+            let b = bin_of(p);
+            bin_counts[b] += 1;
+            bin_bounds[b].join_mut(&p.aabb);
         }
+
+        // sweep from the right to get the cost of every split between bins in linear time
+        let mut right_area = vec![0.0; self.bins];
+        let mut right_count = vec![0usize; self.bins];
+        let mut bounds = Aabb::EMPTY;
+        let mut count = 0;
+        for b in (1..self.bins).rev() {
+            bounds.join_mut(&bin_bounds[b]);
+            count += bin_counts[b];
+            right_area[b] = surface_area(&bounds);
+            right_count[b] = count;
+        }
+
+        let parent_area = surface_area(&aabb);
+        let mut best: Option<(usize, f32)> = None;
+        let mut bounds = Aabb::EMPTY;
+        let mut count = 0;
+        for b in 1..self.bins {
+            bounds.join_mut(&bin_bounds[b - 1]);
+            count += bin_counts[b - 1];
+
+            if count == 0 || right_count[b] == 0 {
+                continue;
+            }
+
+            let cost = TRAVERSAL_COST
+                + (surface_area(&bounds) * count as f32 + right_area[b] * right_count[b] as f32)
+                    / parent_area;
+
+            if cost.is_finite() && best.is_none_or(|(_, c)| cost < c) {
+                best = Some((b, cost));
+            }
+        }
+
+        let Some((split, cost)) = best else {
+            // unbounded primitives make every cost infinite
+            return self.split_median(primitives, axis, aabb, depth);
+        };
+
+        if primitives.len() <= self.max_leaf_size && cost >= primitives.len() as f32 {
+            return leaf(primitives, aabb);
+        }
+
+        let (right, left): (Vec<_>, Vec<_>) =
+            primitives.into_iter().partition(|p| bin_of(p) >= split);
+
+        BuildNode::Interior {
+            left: Box::new(self.build_node(left, depth + 1)),
+            right: Box::new(self.build_node(right, depth + 1)),
+            axis,
+            aabb,
+        }
+    }
+
+    fn split_median(
+        &self,
+        mut primitives: Vec<Primitive>,
+        axis: usize,
+        aabb: Aabb,
+        depth: usize,
+    ) -> BuildNode {
+        primitives.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+        let right = primitives.split_off(primitives.len() / 2);
+
+        BuildNode::Interior {
+            left: Box::new(self.build_node(primitives, depth + 1)),
+            right: Box::new(self.build_node(right, depth + 1)),
+            axis,
+            aabb,
+        }
+    }
+}
+
+fn leaf(primitives: Vec<Primitive>, aabb: Aabb) -> BuildNode {
+    BuildNode::Leaf {
+        hittables: primitives.into_iter().map(|p| p.hittable).collect(),
+        aabb,
     }
 }
 
+// unbounded axes of infinite primitives are treated as centered on the origin
+fn centroid(aabb: &Aabb) -> Vec3 {
+    let mid = |min: f32, max: f32| {
+        let c = (min + max) * 0.5;
+        if c.is_finite() {
+            c
+        } else {
+            0.0
+        }
+    };
+
+    vec3(
+        mid(aabb.min.x, aabb.max.x),
+        mid(aabb.min.y, aabb.max.y),
+        mid(aabb.min.z, aabb.max.z),
+    )
+}
+
+fn surface_area(aabb: &Aabb) -> f32 {
+    let d = aabb.max - aabb.min;
+    2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+}
+
 impl Hittable for BVHNode {
     fn aabb(&self) -> Aabb {
-        match self {
-            BVHNode::Node { aabb, .. } => *aabb,
-            BVHNode::Leaf(h) => h.aabb(),
-        }
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.aabb)
     }
 
-    fn hit(&self, ray: &Ray, ray_tmin: f32, mut ray_tmax: f32) -> Option<HitRecord> {
-        if !self.aabb().hit(ray, ray_tmin, ray_tmax) {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        mut ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        if self.nodes.is_empty() {
             return None;
         }
 
-        match self {
-            BVHNode::Leaf(h) => h.hit(ray, ray_tmin, ray_tmax),
-            BVHNode::Node { left, right, .. } => {
-                let hit_left = left.hit(ray, ray_tmin, ray_tmax);
-                ray_tmax = hit_left.as_ref().map_or(ray_tmax, |rec| rec.t);
-                let hit_right = right.hit(ray, ray_tmin, ray_tmax);
+        let inv_dir = vec3(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        let dir_is_neg = [inv_dir.x < 0.0, inv_dir.y < 0.0, inv_dir.z < 0.0];
+
+        let mut closest = None;
+        let mut stack = [0; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut index = 0;
 
-                if hit_right.is_some() {
-                    hit_right
+        loop {
+            let node = &self.nodes[index];
+
+            if node.aabb.hit(ray, inv_dir, ray_tmin, ray_tmax) {
+                if node.count > 0 {
+                    for hittable in &self.primitives[node.offset..node.offset + node.count] {
+                        if let Some(rec) = hittable.hit(ray, ray_tmin, ray_tmax, sampler) {
+                            ray_tmax = rec.t;
+                            closest = Some(rec);
+                        }
+                    }
                 } else {
-                    hit_left
+                    // visit the child nearer to the ray origin first so farther ones get culled
+                    let (near, far) = if dir_is_neg[node.axis] {
+                        (node.offset, index + 1)
+                    } else {
+                        (index + 1, node.offset)
+                    };
+
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    index = near;
+                    continue;
                 }
             }
+
+            if stack_len == 0 {
+                break;
+            }
+
+            stack_len -= 1;
+            index = stack[stack_len];
         }
+
+        closest
     }
 }
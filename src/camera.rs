@@ -1,17 +1,5 @@
-use crate::math::{random, random_rng, vec3, Ray, Vec3};
-
-fn sample_square() -> Vec3 {
-    vec3(random() - 0.5, random() - 0.5, 0.0)
-}
-
-fn random_in_unit_disk() -> Vec3 {
-    loop {
-        let p = vec3(random_rng(-1.0, 1.0), random_rng(-1.0, 1.0), 0.0);
-        if p.length_squared() < 1.0 {
-            return p;
-        }
-    }
-}
+use crate::math::{sample_unit_disk, Ray, Vec3};
+use crate::sampler::Sampler;
 
 pub struct Camera {
     center: Vec3,
@@ -63,23 +51,23 @@ impl Camera {
         }
     }
 
-    pub fn get_ray(&self, i: u32, j: u32) -> Ray {
-        let offset = sample_square();
+    pub fn get_ray(&self, i: u32, j: u32, sampler: &mut dyn Sampler) -> Ray {
+        let offset = sampler.get_2d();
         let pixel_sample = self.pixel00_loc
-            + (self.pixel_delta_u * (offset.x + i as f32))
-            + (self.pixel_delta_v * (offset.y + j as f32));
+            + (self.pixel_delta_u * (offset.0 - 0.5 + i as f32))
+            + (self.pixel_delta_v * (offset.1 - 0.5 + j as f32));
 
         let origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(sampler.get_2d())
         };
 
-        Ray::new(origin, pixel_sample - origin, random())
+        Ray::new(origin, pixel_sample - origin, sampler.get_1d())
     }
 
-    fn defocus_disk_sample(&self) -> Vec3 {
-        let p = random_in_unit_disk();
+    fn defocus_disk_sample(&self, u: (f32, f32)) -> Vec3 {
+        let p = sample_unit_disk(u);
         self.center + (self.defocus_disk_u * p.x) + (self.defocus_disk_v * p.y)
     }
 }
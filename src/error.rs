@@ -0,0 +1,49 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::scene_file::SceneError;
+
+#[derive(Debug)]
+pub enum Error {
+    Scene(SceneError),
+    Output(PathBuf, image::ImageError),
+    ThreadPool(rayon::ThreadPoolBuildError),
+    // parameters that would otherwise produce a meaningless scene, like a zero checker scale
+    InvalidInput(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Scene(err) => write!(f, "{err}"),
+            Error::Output(path, err) => {
+                write!(f, "failed to write `{}`: {}", path.display(), err)
+            }
+            Error::ThreadPool(err) => write!(f, "failed to build the render thread pool: {err}"),
+            Error::InvalidInput(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Scene(err) => Some(err),
+            Error::Output(_, err) => Some(err),
+            Error::ThreadPool(err) => Some(err),
+            Error::InvalidInput(_) => None,
+        }
+    }
+}
+
+impl From<SceneError> for Error {
+    fn from(err: SceneError) -> Self {
+        Error::Scene(err)
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for Error {
+    fn from(err: rayon::ThreadPoolBuildError) -> Self {
+        Error::ThreadPool(err)
+    }
+}
@@ -2,11 +2,65 @@ use std::sync::Arc;
 
 use crate::aabb::Aabb;
 use crate::material::Material;
-use crate::math::{Ray, Vec3};
+use crate::math::{vec3, Ray, Vec3};
+use crate::sampler::Sampler;
 
-pub trait Hittable {
-    fn hit(&self, ray: &Ray, ray_tmin: f32, ray_tmax: f32) -> Option<HitRecord>;
+pub trait Hittable: Send + Sync {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord>;
     fn aabb(&self) -> Aabb;
+
+    // solid angle density of `random` picking the direction, zero for shapes that can't be sampled
+    fn pdf_value(&self, _origin: Vec3, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    // direction from the origin towards a random point on the shape
+    fn random(&self, _origin: Vec3, _sampler: &mut dyn Sampler) -> Vec3 {
+        vec3(1.0, 0.0, 0.0)
+    }
+}
+
+// lets one object be shared between the world and the list of lights
+impl<H: Hittable + ?Sized> Hittable for Arc<H> {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        (**self).hit(ray, ray_tmin, ray_tmax, sampler)
+    }
+
+    fn aabb(&self) -> Aabb {
+        (**self).aabb()
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        (**self).random(origin, sampler)
+    }
+}
+
+// converts the area density of a point hit on a flat shape into a solid angle density
+pub fn planar_pdf(rec: &HitRecord, direction: Vec3, area: f32) -> f32 {
+    let distance_squared = rec.t * rec.t * direction.length_squared();
+    let cosine = (direction.dot(rec.normal) / direction.length()).abs();
+
+    if cosine < 1e-8 {
+        return 0.0;
+    }
+
+    distance_squared / (cosine * area)
 }
 
 pub struct HittableList {
@@ -30,12 +84,18 @@ impl HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, ray_tmin: f32, ray_tmax: f32) -> Option<HitRecord> {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
         let mut temp_rec = None;
         let mut closest_so_far = ray_tmax;
 
         for hittable in &self.hittables {
-            if let Some(rec) = hittable.hit(ray, ray_tmin, closest_so_far) {
+            if let Some(rec) = hittable.hit(ray, ray_tmin, closest_so_far, sampler) {
                 closest_so_far = rec.t;
                 temp_rec = Some(rec);
             }
@@ -47,6 +107,31 @@ impl Hittable for HittableList {
     fn aabb(&self) -> Aabb {
         self.aabb
     }
+
+    // every member is picked with the same probability
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        if self.hittables.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f32 = self
+            .hittables
+            .iter()
+            .map(|h| h.pdf_value(origin, direction))
+            .sum();
+
+        sum / self.hittables.len() as f32
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let n = self.hittables.len();
+        if n == 0 {
+            return vec3(1.0, 0.0, 0.0);
+        }
+
+        let index = ((sampler.get_1d() * n as f32) as usize).min(n - 1);
+        self.hittables[index].random(origin, sampler)
+    }
 }
 
 pub struct HitRecord {
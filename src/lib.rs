@@ -0,0 +1,29 @@
+pub mod aabb;
+pub mod background;
+pub mod bvh;
+pub mod camera;
+pub mod error;
+pub mod hittable;
+pub mod material;
+pub mod math;
+pub mod obj;
+pub mod objects;
+pub mod render;
+pub mod sampler;
+pub mod scene_file;
+pub mod scenes;
+pub mod texture;
+pub mod tonemap;
+
+pub use background::Background;
+pub use bvh::{BVHBuilder, BVHNode};
+pub use camera::Camera;
+pub use error::Error;
+pub use hittable::{HitRecord, Hittable, HittableList};
+pub use material::{BsdfSample, Material};
+pub use math::Vec3;
+pub use render::{render, render_with_progress, AdaptiveSampling, Framebuffer, RenderSettings};
+pub use sampler::{Sampler, SamplerKind};
+pub use scenes::Scene;
+pub use texture::Texture;
+pub use tonemap::{DisplayTransform, ToneMap};
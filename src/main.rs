@@ -1,30 +1,258 @@
-mod aabb;
-mod bvh;
-mod camera;
-mod hittable;
-mod material;
-mod math;
-mod objects;
-mod render;
-mod scenes;
-mod texture;
-
-const IMAGE_WIDTH: u32 = 400;
-const ASPECT_RATIO: f32 = 16.0 / 9.0;
-const IMAGE_HEIGHT: u32 = (IMAGE_WIDTH as f32 / ASPECT_RATIO) as u32;
-
-const SAMPLES_PER_PIXEL: u32 = 100;
-const MAX_DEPTH: u32 = 50;
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use console::Style;
+use indicatif::{ProgressBar, ProgressStyle};
+use raytracer::math::Rng;
+use raytracer::{
+    scene_file, scenes, AdaptiveSampling, BVHBuilder, DisplayTransform, Error, RenderSettings,
+    SamplerKind, ToneMap,
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Scene {
+    BouncingSpheres,
+    CheckeredSpheres,
+    SimpleLight,
+    PerlinSpheres,
+    CornellBox,
+    CornellSmoke,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Sampler {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl From<Sampler> for SamplerKind {
+    fn from(sampler: Sampler) -> Self {
+        match sampler {
+            Sampler::Independent => SamplerKind::Independent,
+            Sampler::Stratified => SamplerKind::Stratified,
+            Sampler::Halton => SamplerKind::Halton,
+            Sampler::Sobol => SamplerKind::Sobol,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ToneMapper {
+    Clamp,
+    Reinhard,
+    Aces,
+    Agx,
+}
+
+impl From<ToneMapper> for ToneMap {
+    fn from(tone_map: ToneMapper) -> Self {
+        match tone_map {
+            ToneMapper::Clamp => ToneMap::Clamp,
+            ToneMapper::Reinhard => ToneMap::Reinhard,
+            ToneMapper::Aces => ToneMap::Aces,
+            ToneMapper::Agx => ToneMap::Agx,
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(version, about = "a simple raytracer")]
+struct Args {
+    /// Built-in scene to render
+    #[arg(
+        long,
+        value_enum,
+        default_value = "checkered-spheres",
+        conflicts_with = "file"
+    )]
+    scene: Scene,
+
+    /// Scene description file to render instead of a built-in scene
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+
+    /// Image width in pixels
+    #[arg(long, default_value_t = 400)]
+    width: u32,
+
+    /// Image height in pixels [default: width for a 16:9 aspect ratio]
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// Samples per pixel, the upper bound when sampling adaptively
+    #[arg(short, long, default_value_t = 100)]
+    samples: u32,
+
+    /// Stop sampling a pixel once its relative noise falls below this threshold
+    #[arg(long)]
+    noise_threshold: Option<f32>,
+
+    /// Samples every pixel gets per adaptive sampling pass
+    #[arg(long, default_value_t = 16, requires = "noise_threshold")]
+    min_samples: u32,
+
+    /// Also write an image showing the samples spent on each pixel
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+
+    /// Sample sequence used for pixel, lens, time and scattering dimensions
+    #[arg(long, value_enum, default_value = "sobol")]
+    sampler: Sampler,
+
+    /// Maximum number of bounces per path
+    #[arg(short, long, default_value_t = 50)]
+    depth: u32,
+
+    /// Bounces before paths may be ended early by russian roulette
+    #[arg(long, default_value_t = 3)]
+    roulette_depth: u32,
+
+    /// Output image path, .exr, .hdr and .pfm keep the unclipped linear values
+    #[arg(short, long, default_value = "image.png")]
+    output: PathBuf,
+
+    /// Exposure adjustment in stops applied before tone mapping
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f32,
+
+    /// Curve mapping bright values into the displayable range of 8-bit images
+    #[arg(long, value_enum, default_value = "clamp")]
+    tone_map: ToneMapper,
+
+    /// Seed for the random numbers used by scene layouts and sampling
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of render threads [default: all cores]
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Maximum number of primitives in a BVH leaf
+    #[arg(long, default_value_t = 4)]
+    leaf_size: usize,
+
+    /// Number of bins evaluated per split when building the BVH
+    #[arg(long, default_value_t = 16)]
+    bvh_bins: usize,
+
+    /// Print statistics about the built BVH
+    #[arg(long)]
+    bvh_stats: bool,
+}
 
 fn main() {
-    let (world, camera) = scenes::checkered_spheres(IMAGE_WIDTH, IMAGE_HEIGHT);
+    let args = Args::parse();
 
-    render::render(
-        camera,
+    if let Err(err) = run(args) {
+        eprintln!("{} {err}", Style::new().red().bold().apply_to("error:"));
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    let width = args.width.max(1);
+    let height = args
+        .height
+        .unwrap_or((width as f32 * 9.0 / 16.0) as u32)
+        .max(1);
+    let size = (width, height);
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    let scenes::Scene {
         world,
-        (IMAGE_WIDTH, IMAGE_HEIGHT),
-        SAMPLES_PER_PIXEL,
-        MAX_DEPTH,
-        "image.png",
+        lights,
+        camera,
+        background,
+    } = match &args.file {
+        Some(path) => scene_file::load(path, size)?,
+        None => match args.scene {
+            Scene::BouncingSpheres => {
+                scenes::bouncing_spheres(width, height, &mut Rng::new(args.seed))?
+            }
+            Scene::CheckeredSpheres => scenes::checkered_spheres(width, height)?,
+            Scene::SimpleLight => scenes::simple_light(width, height)?,
+            Scene::PerlinSpheres => scenes::perlin_spheres(width, height)?,
+            Scene::CornellBox => scenes::cornell_box(width, height)?,
+            Scene::CornellSmoke => scenes::cornell_smoke(width, height)?,
+        },
+    };
+
+    let mut objects = world.hittables;
+    let world = BVHBuilder::new()
+        .max_leaf_size(args.leaf_size)
+        .bins(args.bvh_bins)
+        .build(&mut objects);
+
+    if args.bvh_stats {
+        println!(
+            "{} {}",
+            Style::new().cyan().bold().apply_to("BVH"),
+            world.stats()
+        );
+    }
+
+    let settings = RenderSettings {
+        size,
+        samples_per_pixel: args.samples,
+        adaptive: args
+            .noise_threshold
+            .map(|noise_threshold| AdaptiveSampling {
+                min_samples: args.min_samples,
+                noise_threshold,
+            }),
+        max_depth: args.depth,
+        roulette_depth: args.roulette_depth,
+        sampler: args.sampler.into(),
+        seed: args.seed,
+    };
+
+    let pb = ProgressBar::new(height as u64 * settings.passes() as u64);
+    pb.set_style(
+        ProgressStyle::with_template("{prefix:.cyan.bold} [{bar:25}] {percent_precise}%")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    pb.set_prefix("Rendering");
+
+    let framebuffer =
+        raytracer::render_with_progress(&camera, &world, &lights, &background, &settings, || {
+            pb.inc(1)
+        });
+
+    pb.finish_and_clear();
+    let display = DisplayTransform {
+        exposure: args.exposure,
+        tone_map: args.tone_map.into(),
+    };
+    framebuffer.save(&args.output, &display)?;
+
+    println!(
+        "{} to `{}` in {:?}",
+        Style::new().green().bold().apply_to("Rendered"),
+        args.output.display(),
+        pb.elapsed()
     );
+
+    if args.noise_threshold.is_some() {
+        println!(
+            "{} {:.1} samples per pixel on average",
+            Style::new().cyan().bold().apply_to("Adaptive"),
+            framebuffer.average_samples()
+        );
+    }
+
+    if let Some(path) = &args.heatmap {
+        framebuffer
+            .sample_heatmap()
+            .save(path, &DisplayTransform::default())?;
+    }
+
+    Ok(())
 }
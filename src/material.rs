@@ -1,9 +1,40 @@
+use crate::error::Error;
 use crate::hittable::HitRecord;
-use crate::math::{random, random_unit_vector, Ray, Vec3};
+use crate::math::{sample_cosine_power, sample_unit_sphere, Onb, Ray, Vec3, PI};
+use crate::sampler::Sampler;
 use crate::texture::{Checkered, Solid, Texture};
 
-pub trait Material {
-    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)>;
+// a scattered ray picked by a material
+pub struct BsdfSample {
+    // brdf times the cosine term divided by the pdf
+    pub attenuation: Vec3,
+    pub scattered: Ray,
+    // solid angle density of the scattered direction, none for mirror-like scattering
+    pub pdf: Option<f32>,
+}
+
+pub trait Material: Send + Sync {
+    fn sample(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample>;
+
+    fn emitted(&self, _uv: (f32, f32), _point: &Vec3) -> Vec3 {
+        Vec3::ZEROS
+    }
+
+    // brdf times the cosine term for light arriving from `direction`, none for
+    // materials like mirrors and glass whose scattering can't be evaluated directly
+    fn eval(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Option<Vec3> {
+        None
+    }
+
+    // density with which `sample` picks `direction`
+    fn pdf(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f32 {
+        0.0
+    }
 }
 
 pub struct Lambertian {
@@ -19,28 +50,43 @@ impl Lambertian {
         Self::new(Box::new(Solid::new(albedo)))
     }
 
-    pub fn checkered(scale: f32, even_albedo: Vec3, odd_albedo: Vec3) -> Self {
-        Self::new(Box::new(Checkered::new(
+    pub fn checkered(scale: f32, even_albedo: Vec3, odd_albedo: Vec3) -> Result<Self, Error> {
+        Ok(Self::new(Box::new(Checkered::new(
             scale,
             Box::new(Solid::new(even_albedo)),
             Box::new(Solid::new(odd_albedo)),
-        )))
+        )?)))
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        let mut scatter_direction = rec.normal + random_unit_vector();
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let mut scatter_direction = rec.normal + sample_unit_sphere(sampler.get_2d());
 
+        // a sample opposite the normal cancels it out and leaves no direction
         let s = 1e-8;
-        if scatter_direction.x < s && scatter_direction.y < s && scatter_direction.z < s {
+        if scatter_direction.x.abs() < s
+            && scatter_direction.y.abs() < s
+            && scatter_direction.z.abs() < s
+        {
             scatter_direction = rec.normal;
         }
 
-        Some((
-            self.texture.value(rec.uv, &rec.point),
-            Ray::new(rec.point, scatter_direction, r_in.time),
-        ))
+        // the offset unit vector gives cosine weighted directions
+        Some(BsdfSample {
+            attenuation: self.texture.value(rec.uv, &rec.point),
+            scattered: Ray::new(rec.point, scatter_direction, r_in.time),
+            pdf: Some(self.pdf(r_in, rec, scatter_direction)),
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Option<Vec3> {
+        let cosine = rec.normal.dot(direction.normalize()).max(0.0);
+        Some(self.texture.value(rec.uv, &rec.point) * (cosine / PI))
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f32 {
+        rec.normal.dot(direction.normalize()).max(0.0) / PI
     }
 }
 
@@ -56,18 +102,67 @@ impl Metal {
             fuzz: fuzz.min(1.0),
         }
     }
+
+    // the fuzz maps onto the exponent of a normalized phong lobe around the mirror direction
+    fn exponent(&self) -> f32 {
+        2.0 / (self.fuzz * self.fuzz) - 2.0
+    }
+
+    // cosine to the mirror direction raised to the exponent, over 2 pi, shared by eval and pdf
+    fn lobe(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f32 {
+        let reflected = reflect(r_in.direction.normalize(), rec.normal);
+        let cosine = reflected.dot(direction.normalize()).max(0.0);
+
+        cosine.powf(self.exponent()) / (2.0 * PI)
+    }
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
-        let reflected =
-            reflect(r_in.direction, rec.normal).normalize() + (random_unit_vector() * self.fuzz);
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let reflected = reflect(r_in.direction.normalize(), rec.normal);
 
-        if reflected.dot(rec.normal) > 0.0 {
-            Some((self.albedo, Ray::new(rec.point, reflected, r_in.time)))
-        } else {
-            None
+        if self.fuzz <= 0.0 {
+            return (reflected.dot(rec.normal) > 0.0).then(|| BsdfSample {
+                attenuation: self.albedo,
+                scattered: Ray::new(rec.point, reflected, r_in.time),
+                pdf: None,
+            });
+        }
+
+        let exponent = self.exponent();
+        let direction =
+            Onb::new(reflected).transform(sample_cosine_power(sampler.get_2d(), exponent));
+
+        // directions below the surface are absorbed
+        let cosine = direction.dot(rec.normal);
+        if cosine <= 0.0 {
+            return None;
+        }
+
+        Some(BsdfSample {
+            attenuation: self.albedo * ((exponent + 2.0) / (exponent + 1.0) * cosine),
+            scattered: Ray::new(rec.point, direction, r_in.time),
+            pdf: Some(self.pdf(r_in, rec, direction)),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Option<Vec3> {
+        if self.fuzz <= 0.0 {
+            return None;
         }
+
+        let cosine = rec.normal.dot(direction.normalize()).max(0.0);
+        let lobe = self.lobe(r_in, rec, direction);
+
+        Some(self.albedo * ((self.exponent() + 2.0) * lobe * cosine))
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f32 {
+        if self.fuzz <= 0.0 {
+            return 0.0;
+        }
+
+        (self.exponent() + 1.0) * self.lobe(r_in, rec, direction)
     }
 }
 
@@ -82,7 +177,7 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
         let ri = if rec.front_face {
             1.0 / self.refraction_index
         } else {
@@ -94,14 +189,80 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let direction = {
-            if ri * sin_theta > 1.0 || reflectance(cos_theta, ri) > random() {
+            if ri * sin_theta > 1.0 || reflectance(cos_theta, ri) > sampler.get_1d() {
                 reflect(unit_direction, rec.normal)
             } else {
                 refract(unit_direction, rec.normal, ri)
             }
         };
 
-        Some((Vec3::ONES, Ray::new(rec.point, direction, r_in.time)))
+        Some(BsdfSample {
+            attenuation: Vec3::ONES,
+            scattered: Ray::new(rec.point, direction, r_in.time),
+            pdf: None,
+        })
+    }
+}
+
+pub struct DiffuseLight {
+    texture: Box<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(texture: Box<dyn Texture>) -> Self {
+        Self { texture }
+    }
+
+    pub fn solid(emit: Vec3) -> Self {
+        Self::new(Box::new(Solid::new(emit)))
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(
+        &self,
+        _r_in: &Ray,
+        _rec: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, uv: (f32, f32), point: &Vec3) -> Vec3 {
+        self.texture.value(uv, point)
+    }
+}
+
+// scatters uniformly in every direction, used as the phase function of volumes
+pub struct Isotropic {
+    texture: Box<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(texture: Box<dyn Texture>) -> Self {
+        Self { texture }
+    }
+
+    pub fn solid(albedo: Vec3) -> Self {
+        Self::new(Box::new(Solid::new(albedo)))
+    }
+}
+
+impl Material for Isotropic {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        Some(BsdfSample {
+            attenuation: self.texture.value(rec.uv, &rec.point),
+            scattered: Ray::new(rec.point, sample_unit_sphere(sampler.get_2d()), r_in.time),
+            pdf: Some(1.0 / (4.0 * PI)),
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, _direction: Vec3) -> Option<Vec3> {
+        Some(self.texture.value(rec.uv, &rec.point) / (4.0 * PI))
+    }
+
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3) -> f32 {
+        1.0 / (4.0 * PI)
     }
 }
 
@@ -0,0 +1,213 @@
+use std::ops::Mul;
+
+use crate::math::{vec3, Vec3};
+
+// row-major affine transform acting on column vectors, so `a * b` applies b first
+#[derive(Clone, Copy)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut result = Self::IDENTITY;
+        result.m[0][3] = offset.x;
+        result.m[1][3] = offset.y;
+        result.m[2][3] = offset.z;
+        result
+    }
+
+    pub fn scale(factors: Vec3) -> Self {
+        let mut result = Self::IDENTITY;
+        result.m[0][0] = factors.x;
+        result.m[1][1] = factors.y;
+        result.m[2][2] = factors.z;
+        result
+    }
+
+    // rotation by angle radians around an arbitrary axis (rodrigues' formula)
+    pub fn rotation(axis: Vec3, angle: f32) -> Self {
+        let a = axis.normalize();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+
+        Self {
+            m: [
+                [
+                    t * a.x * a.x + cos,
+                    t * a.x * a.y - sin * a.z,
+                    t * a.x * a.z + sin * a.y,
+                    0.0,
+                ],
+                [
+                    t * a.x * a.y + sin * a.z,
+                    t * a.y * a.y + cos,
+                    t * a.y * a.z - sin * a.x,
+                    0.0,
+                ],
+                [
+                    t * a.x * a.z - sin * a.y,
+                    t * a.y * a.z + sin * a.x,
+                    t * a.z * a.z + cos,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    pub fn rotation_x(angle: f32) -> Self {
+        Self::rotation(vec3(1.0, 0.0, 0.0), angle)
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        Self::rotation(vec3(0.0, 1.0, 0.0), angle)
+    }
+
+    pub fn rotation_z(angle: f32) -> Self {
+        Self::rotation(vec3(0.0, 0.0, 1.0), angle)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = *self;
+        for (i, row) in result.m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        result
+    }
+
+    // gauss-jordan elimination with partial pivoting, none if the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::IDENTITY.m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))
+                .unwrap_or(col);
+
+            // pivots are compared against the magnitude of their column, so uniformly
+            // tiny or huge scales still invert while rounding residue counts as zero
+            let magnitude = (0..4).map(|row| self.m[row][col].abs()).fold(0.0, f32::max);
+            if a[pivot][col].abs() <= magnitude * f32::EPSILON * 16.0 {
+                return None;
+            }
+
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for k in 0..4 {
+                        a[row][k] -= factor * a[col][k];
+                        inv[row][k] -= factor * inv[col][k];
+                    }
+                }
+            }
+        }
+
+        Some(Self { m: inv })
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let m = &self.m;
+        vec3(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    // ignores the translation part
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        vec3(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul<Mat4> for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Mat4) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+
+        Self { m }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_identity(m: Mat4, tolerance: f32) {
+        for (i, row) in m.m.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!(
+                    (value - expected).abs() < tolerance,
+                    "entry ({i}, {j}) is {value}, expected {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_of_composed_transform() {
+        let m = Mat4::translation(vec3(3.0, -2.0, 5.0))
+            * Mat4::rotation(vec3(1.0, 2.0, 3.0), 0.7)
+            * Mat4::scale(vec3(2.0, 0.5, 4.0));
+        let inverse = m.inverse().unwrap();
+
+        assert_identity(m * inverse, 1e-5);
+        assert_identity(inverse * m, 1e-5);
+    }
+
+    #[test]
+    fn inverse_of_tiny_and_huge_scales() {
+        for factor in [1e-4, 1e4] {
+            let m = Mat4::translation(vec3(100.0, 200.0, -300.0))
+                * Mat4::rotation_y(0.3)
+                * Mat4::scale(Vec3::from_v(factor));
+
+            // the translation column rounds relative to its entries of a few hundred
+            assert_identity(m * m.inverse().unwrap(), 1e-4);
+        }
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        let flat = Mat4::rotation_x(0.5) * Mat4::scale(vec3(1.0, 0.0, 1.0));
+        assert!(flat.inverse().is_none());
+
+        let mut repeated = Mat4::IDENTITY;
+        repeated.m[0] = repeated.m[1];
+        assert!(repeated.inverse().is_none());
+    }
+}
@@ -1,8 +1,12 @@
+pub mod mat4;
+pub mod onb;
+pub mod perlin;
 pub mod random;
 pub mod ray;
+pub mod sampling;
 pub mod vec3;
 
-pub use {random::*, ray::*, vec3::*};
+pub use {mat4::*, onb::*, perlin::*, random::*, ray::*, sampling::*, vec3::*};
 
 pub const INFINITY: f32 = f32::INFINITY;
 pub const PI: f32 = std::f32::consts::PI;
@@ -11,10 +15,25 @@ pub const fn degrees_to_radians(degrees: f32) -> f32 {
     degrees * PI / 180.0
 }
 
-pub fn linear_to_gamma(linear_component: f32) -> f32 {
-    if linear_component > 0.0 {
-        linear_component.sqrt()
+// exact piecewise srgb encoding
+pub fn linear_to_srgb(linear: f32) -> f32 {
+    if linear <= 0.0031308 {
+        (linear * 12.92).max(0.0)
     } else {
-        0.0
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// relative luminance of a linear rec. 709 color
+pub fn luminance(color: Vec3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+// exact piecewise srgb decoding
+pub fn srgb_to_linear(encoded: f32) -> f32 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
     }
 }
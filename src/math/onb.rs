@@ -0,0 +1,29 @@
+use crate::math::{vec3, Vec3};
+
+// orthonormal basis with w along the given direction
+#[derive(Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Self {
+        let w = n.normalize();
+        let a = if w.x.abs() > 0.9 {
+            vec3(0.0, 1.0, 0.0)
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(a).normalize();
+        let u = w.cross(v);
+
+        Self { u, v, w }
+    }
+
+    // from coordinates in this basis to world space
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        self.u * v.x + self.v * v.y + self.w * v.z
+    }
+}
@@ -0,0 +1,113 @@
+use crate::math::{vec3, Rng, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+// gradient noise with permutation tables drawn from a fixed seed, so results are stable across runs
+pub struct Perlin {
+    gradients: [Vec3; POINT_COUNT],
+    perm_x: [usize; POINT_COUNT],
+    perm_y: [usize; POINT_COUNT],
+    perm_z: [usize; POINT_COUNT],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+
+        let mut gradients = [Vec3::ZEROS; POINT_COUNT];
+        for gradient in &mut gradients {
+            *gradient = loop {
+                let p = rng.random_vec3(-1.0, 1.0);
+
+                if p.length_squared() > 1e-6 && p.length_squared() <= 1.0 {
+                    break p.normalize();
+                }
+            };
+        }
+
+        Self {
+            gradients,
+            perm_x: permutation(&mut rng),
+            perm_y: permutation(&mut rng),
+            perm_z: permutation(&mut rng),
+        }
+    }
+
+    // roughly in -1..1
+    pub fn noise(&self, point: &Vec3) -> f32 {
+        let u = point.x - point.x.floor();
+        let v = point.y - point.y.floor();
+        let w = point.z - point.z.floor();
+
+        let i = point.x.floor() as i32;
+        let j = point.y.floor() as i32;
+        let k = point.z.floor() as i32;
+
+        let mut c = [[[Vec3::ZEROS; 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, gradient) in row.iter_mut().enumerate() {
+                    *gradient = self.gradients[self.perm_x[((i + di as i32) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i32) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i32) & 255) as usize]];
+                }
+            }
+        }
+
+        trilinear_interp(&c, u, v, w)
+    }
+
+    // sum of octaves with halving weights and doubling frequencies
+    pub fn turbulence(&self, point: &Vec3, depth: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut temp_point = *point;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_point);
+            weight *= 0.5;
+            temp_point = temp_point * 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+// fisher-yates shuffle of 0..POINT_COUNT
+fn permutation(rng: &mut Rng) -> [usize; POINT_COUNT] {
+    let mut p = [0; POINT_COUNT];
+    for (i, v) in p.iter_mut().enumerate() {
+        *v = i;
+    }
+
+    for i in (1..POINT_COUNT).rev() {
+        let j = rng.random_range(0.0, (i + 1) as f32) as usize;
+        p.swap(i, j);
+    }
+
+    p
+}
+
+fn trilinear_interp(c: &[[[Vec3; 2]; 2]; 2], u: f32, v: f32, w: f32) -> f32 {
+    // hermite smoothing hides the grid artifacts
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+
+    let mut accum = 0.0;
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f32, j as f32, k as f32);
+                let weight = vec3(u - fi, v - fj, w - fk);
+
+                accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * gradient.dot(weight);
+            }
+        }
+    }
+
+    accum
+}
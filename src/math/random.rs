@@ -1,30 +1,58 @@
-use rand::{thread_rng, Rng};
-
 use crate::math::{vec3, Vec3};
 
-pub fn random_rng(min: f32, max: f32) -> f32 {
-    let mut rng = thread_rng();
-    rng.gen_range(min..max)
-}
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const PCG_INCREMENT: u64 = 1442695040888963407;
 
-pub fn random() -> f32 {
-    random_rng(0.0, 1.0)
+// small pcg32 generator, its output only depends on the seed so renders are reproducible
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
 }
 
-pub fn random_vec3(min: f32, max: f32) -> Vec3 {
-    vec3(
-        random_rng(min, max),
-        random_rng(min, max),
-        random_rng(min, max),
-    )
-}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(PCG_INCREMENT);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
 
-pub fn random_unit_vector() -> Vec3 {
-    loop {
-        let p = random_vec3(-1.0, 1.0);
+    // uniform in [0, 1)
+    pub fn random(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn random_range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.random()
+    }
 
-        if p.length_squared() <= 1.0 {
-            return p / p.length();
-        }
+    pub fn random_vec3(&mut self, min: f32, max: f32) -> Vec3 {
+        vec3(
+            self.random_range(min, max),
+            self.random_range(min, max),
+            self.random_range(min, max),
+        )
     }
 }
+
+// splitmix64 finalizer
+pub fn mix_bits(mut v: u64) -> u64 {
+    v = (v ^ (v >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    v = (v ^ (v >> 27)).wrapping_mul(0x94d049bb133111eb);
+    v ^ (v >> 31)
+}
+
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e3779b97f4a7c15, |h: u64, &value| {
+        mix_bits((h ^ value).wrapping_add(0x9e3779b97f4a7c15))
+    })
+}
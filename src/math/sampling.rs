@@ -0,0 +1,36 @@
+use crate::math::{vec3, Vec3, PI};
+
+// warps a uniform 2d sample onto the unit disk, keeping neighbouring samples close together
+pub fn sample_unit_disk(u: (f32, f32)) -> Vec3 {
+    let (a, b) = (2.0 * u.0 - 1.0, 2.0 * u.1 - 1.0);
+
+    if a == 0.0 && b == 0.0 {
+        return Vec3::ZEROS;
+    }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+
+    vec3(r * theta.cos(), r * theta.sin(), 0.0)
+}
+
+// uniform direction on the unit sphere
+pub fn sample_unit_sphere(u: (f32, f32)) -> Vec3 {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+
+    vec3(r * phi.cos(), r * phi.sin(), z)
+}
+
+// direction around +z with a density proportional to cos(theta)^exponent
+pub fn sample_cosine_power(u: (f32, f32), exponent: f32) -> Vec3 {
+    let cos_theta = u.0.powf(1.0 / (exponent + 1.0));
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+
+    vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
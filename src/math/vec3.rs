@@ -48,6 +48,12 @@ impl Vec3 {
     }
 }
 
+impl From<[f32; 3]> for Vec3 {
+    fn from(v: [f32; 3]) -> Self {
+        Self::new(v[0], v[1], v[2])
+    }
+}
+
 impl Add<Vec3> for Vec3 {
     type Output = Self;
 
@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::hittable::Hittable;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::math::{vec3, Vec3};
+use crate::objects::triangle::is_degenerate;
+use crate::objects::Mesh;
+use crate::texture::ImageTexture;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, std::io::Error),
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ObjError::Invalid {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+struct Parser<'a> {
+    path: &'a Path,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> ObjError {
+        ObjError::Invalid {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn floats<const N: usize>(&self, args: &[&str]) -> Result<[f32; N], ObjError> {
+        // trailing optional components (like the w of a vertex) are ignored
+        if args.len() < N {
+            return Err(self.error(format!("expected {} numbers, found {}", N, args.len())));
+        }
+
+        let mut values = [0.0; N];
+        for (value, arg) in values.iter_mut().zip(args) {
+            *value = arg
+                .parse()
+                .map_err(|_| self.error(format!("invalid number `{arg}`")))?;
+        }
+
+        Ok(values)
+    }
+
+    fn vec3(&self, args: &[&str]) -> Result<Vec3, ObjError> {
+        self.floats::<3>(args).map(Vec3::from)
+    }
+
+    fn single(&self, args: &[&str]) -> Result<f32, ObjError> {
+        self.floats::<1>(args).map(|[v]| v)
+    }
+
+    // obj indices are 1-based, negative ones count back from the most recent element
+    fn index(&self, arg: &str, count: usize) -> Result<usize, ObjError> {
+        let index: isize = arg
+            .parse()
+            .map_err(|_| self.error(format!("invalid index `{arg}`")))?;
+
+        let resolved = match index {
+            i if i > 0 => i - 1,
+            i if i < 0 => count as isize + i,
+            _ => return Err(self.error("indices start at 1")),
+        };
+
+        if resolved < 0 || resolved as usize >= count {
+            return Err(self.error(format!("index `{arg}` out of range")));
+        }
+
+        Ok(resolved as usize)
+    }
+
+    fn face_vertex(
+        &self,
+        arg: &str,
+        counts: (usize, usize, usize),
+    ) -> Result<FaceVertex, ObjError> {
+        let mut parts = arg.split('/');
+
+        let position = self.index(parts.next().unwrap_or(""), counts.0)?;
+        let uv = match parts.next() {
+            Some("") | None => None,
+            Some(i) => Some(self.index(i, counts.1)?),
+        };
+        let normal = match parts.next() {
+            Some("") | None => None,
+            Some(i) => Some(self.index(i, counts.2)?),
+        };
+
+        Ok((position, uv, normal))
+    }
+}
+
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct MtlDesc {
+    diffuse: Option<Vec3>,
+    specular: Option<Vec3>,
+    emission: Option<Vec3>,
+    shininess: Option<f32>,
+    refraction_index: Option<f32>,
+    dissolve: Option<f32>,
+    illum: Option<u32>,
+    // the texture path together with the line it was given on
+    diffuse_map: Option<(PathBuf, usize)>,
+}
+
+impl MtlDesc {
+    fn build(self, path: &Path) -> Result<Arc<dyn Material>, ObjError> {
+        if let Some(emission) = self.emission.filter(|e| e.length_squared() > 0.0) {
+            return Ok(Arc::new(DiffuseLight::solid(emission)));
+        }
+
+        let transparent = self.dissolve.is_some_and(|d| d < 1.0);
+        if transparent || matches!(self.illum, Some(4 | 6 | 7 | 9)) {
+            return Ok(Arc::new(Dielectric::new(
+                self.refraction_index.unwrap_or(1.5),
+            )));
+        }
+
+        if matches!(self.illum, Some(3 | 5)) {
+            // map the phong exponent onto a roughness-like fuzz
+            let fuzz = (2.0 / (self.shininess.unwrap_or(0.0) + 2.0)).sqrt();
+
+            return Ok(Arc::new(Metal::new(
+                self.specular.unwrap_or(Vec3::ONES),
+                fuzz,
+            )));
+        }
+
+        match self.diffuse_map {
+            Some((map, line)) => {
+                let texture = ImageTexture::load(&map).map_err(|err| ObjError::Invalid {
+                    path: path.to_path_buf(),
+                    line,
+                    message: format!("failed to load texture `{}`: {}", map.display(), err),
+                })?;
+
+                Ok(Arc::new(Lambertian::new(Box::new(texture))))
+            }
+            None => Ok(Arc::new(Lambertian::solid(
+                self.diffuse.unwrap_or(vec3(0.8, 0.8, 0.8)),
+            ))),
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|err| ObjError::Io(path.to_path_buf(), err))
+}
+
+// paths inside obj and mtl files are relative to the file referencing them
+fn resolve(path: &Path, name: &str) -> PathBuf {
+    path.parent()
+        .map_or_else(|| PathBuf::from(name), |dir| dir.join(name))
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let source = read(path)?;
+    let mut parser = Parser { path, line: 0 };
+
+    let mut descs: Vec<(String, MtlDesc)> = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        parser.line = number + 1;
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            let name = args.join(" ");
+            if name.is_empty() {
+                return Err(parser.error("`newmtl` needs a name"));
+            }
+
+            descs.push((name, MtlDesc::default()));
+            continue;
+        }
+
+        let Some((_, desc)) = descs.last_mut() else {
+            return Err(parser.error(format!("`{keyword}` before any `newmtl`")));
+        };
+
+        match keyword {
+            "Kd" => desc.diffuse = Some(parser.vec3(&args)?),
+            "Ks" => desc.specular = Some(parser.vec3(&args)?),
+            "Ke" => desc.emission = Some(parser.vec3(&args)?),
+            "Ns" => desc.shininess = Some(parser.single(&args)?),
+            "Ni" => desc.refraction_index = Some(parser.single(&args)?),
+            "d" => desc.dissolve = Some(parser.single(&args)?),
+            "Tr" => desc.dissolve = Some(1.0 - parser.single(&args)?),
+            "illum" => {
+                desc.illum = Some(
+                    args.first()
+                        .and_then(|a| a.parse().ok())
+                        .ok_or_else(|| parser.error("invalid illumination model"))?,
+                )
+            }
+            // options like `-bm 1.0` may precede the file name, which always comes last
+            "map_Kd" => match args.last() {
+                Some(name) => desc.diffuse_map = Some((resolve(path, name), parser.line)),
+                None => return Err(parser.error("`map_Kd` needs a file name")),
+            },
+            _ => {}
+        }
+    }
+
+    descs
+        .into_iter()
+        .map(|(name, desc)| Ok((name, desc.build(path)?)))
+        .collect()
+}
+
+// faces sharing a group and material end up in the same mesh
+struct MeshBuilder {
+    material: Arc<dyn Material>,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    has_normals: bool,
+    has_uvs: bool,
+    indices: Vec<[u32; 3]>,
+    lookup: HashMap<FaceVertex, u32>,
+}
+
+impl MeshBuilder {
+    fn new(material: Arc<dyn Material>) -> Self {
+        Self {
+            material,
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            has_normals: true,
+            has_uvs: true,
+            indices: Vec::new(),
+            lookup: HashMap::new(),
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        vertex: FaceVertex,
+        positions: &[Vec3],
+        uvs: &[(f32, f32)],
+        normals: &[Vec3],
+    ) -> u32 {
+        if let Some(&index) = self.lookup.get(&vertex) {
+            return index;
+        }
+
+        let (position, uv, normal) = vertex;
+        let index = self.positions.len() as u32;
+
+        self.positions.push(positions[position]);
+        // attributes are only kept if every vertex of the mesh has them
+        self.has_uvs &= uv.is_some();
+        self.uvs.push(uv.map_or((0.0, 0.0), |i| uvs[i]));
+        self.has_normals &= normal.is_some();
+        self.normals
+            .push(normal.map_or(Vec3::ZEROS, |i| normals[i]));
+
+        self.lookup.insert(vertex, index);
+        index
+    }
+
+    // errors are reported at the line that ended the mesh
+    fn build(self, parser: &Parser) -> Result<Option<Box<dyn Hittable>>, ObjError> {
+        if self.indices.is_empty() {
+            return Ok(None);
+        }
+
+        let mesh = Mesh::new(
+            self.positions,
+            self.has_normals.then_some(self.normals),
+            self.has_uvs.then_some(self.uvs),
+            self.indices,
+            self.material,
+        )
+        .map_err(|err| parser.error(err.to_string()))?;
+
+        Ok(Some(Box::new(mesh)))
+    }
+}
+
+pub fn load<P: AsRef<Path>>(
+    path: P,
+    default_material: Arc<dyn Material>,
+) -> Result<Vec<Box<dyn Hittable>>, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let mut parser = Parser { path, line: 0 };
+
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut meshes = Vec::new();
+    let mut current = MeshBuilder::new(Arc::clone(&default_material));
+
+    for (number, line) in source.lines().enumerate() {
+        parser.line = number + 1;
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => positions.push(parser.vec3(&args)?),
+            "vn" => normals.push(parser.vec3(&args)?),
+            "vt" => {
+                // the v coordinate is optional for 1d textures
+                let u = parser.single(&args)?;
+                let v = match args.get(1) {
+                    Some(_) => parser.floats::<2>(&args)?[1],
+                    None => 0.0,
+                };
+                uvs.push((u, v));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(parser.error("faces need at least three vertices"));
+                }
+
+                let counts = (positions.len(), uvs.len(), normals.len());
+                let vertices = args
+                    .iter()
+                    .map(|arg| parser.face_vertex(arg, counts))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let indices: Vec<u32> = vertices
+                    .into_iter()
+                    .map(|v| current.vertex(v, &positions, &uvs, &normals))
+                    .collect();
+
+                // polygons are triangulated as a fan around their first vertex, slivers
+                // without any area are dropped since they have no normal to shade with
+                for k in 1..indices.len() - 1 {
+                    let face = [indices[0], indices[k], indices[k + 1]];
+                    let [p0, p1, p2] = face.map(|i| current.positions[i as usize]);
+
+                    if !is_degenerate(p0, p1, p2) {
+                        current.indices.push(face);
+                    }
+                }
+            }
+            "g" | "o" => {
+                let material = Arc::clone(&current.material);
+                meshes.extend(
+                    std::mem::replace(&mut current, MeshBuilder::new(material)).build(&parser)?,
+                );
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                let material = match materials.get(&name) {
+                    Some(material) => Arc::clone(material),
+                    None => return Err(parser.error(format!("unknown material `{name}`"))),
+                };
+
+                meshes.extend(
+                    std::mem::replace(&mut current, MeshBuilder::new(material)).build(&parser)?,
+                );
+            }
+            "mtllib" => {
+                for name in &args {
+                    materials.extend(load_mtl(&resolve(path, name))?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    meshes.extend(current.build(&parser)?);
+
+    if meshes.is_empty() {
+        parser.line = source.lines().count();
+        return Err(parser.error("file does not contain any faces"));
+    }
+
+    Ok(meshes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::{HitRecord, HittableList};
+    use crate::math::{Ray, INFINITY, PI};
+    use crate::sampler::SamplerKind;
+
+    // writes the sources into a fresh directory, so `mtllib test.mtl` resolves next to the obj
+    fn load_source(name: &str, obj: &str, mtl: Option<&str>) -> Result<HittableList, ObjError> {
+        let dir = std::env::temp_dir().join(format!("obj_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("test.obj"), obj).unwrap();
+        if let Some(mtl) = mtl {
+            std::fs::write(dir.join("test.mtl"), mtl).unwrap();
+        }
+
+        let default_material = Arc::new(Lambertian::solid(vec3(0.5, 0.5, 0.5)));
+        let result = load(dir.join("test.obj"), default_material);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        result.map(HittableList::new)
+    }
+
+    // looks straight down the z axis onto the point (x, y, 0)
+    fn hit_at(meshes: &HittableList, x: f32, y: f32) -> Option<HitRecord> {
+        let mut sampler = SamplerKind::Independent.create(1, 0);
+        let ray = Ray::new(vec3(x, y, 1.0), vec3(0.0, 0.0, -1.0), 0.0);
+        meshes.hit(&ray, 0.001, INFINITY, sampler.as_mut())
+    }
+
+    fn error_line(result: Result<HittableList, ObjError>) -> (usize, String) {
+        match result {
+            Err(ObjError::Invalid { line, message, .. }) => (line, message),
+            Err(ObjError::Io(path, err)) => {
+                panic!("unexpected io error for {}: {err}", path.display())
+            }
+            Ok(_) => panic!("loaded without an error"),
+        }
+    }
+
+    fn assert_close(a: (f32, f32), b: (f32, f32)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    const TRIANGLE: &str = "v 5 5 5\nv 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    #[test]
+    fn negative_indices_count_back_from_the_latest_element() {
+        let obj = format!("{TRIANGLE}vt 9 9\nvt 0 0\nvt 2 0\nvt 0 2\nf -3/-3 -2/-2 -1/-1\n");
+        let meshes = load_source("negative", &obj, None).unwrap();
+
+        // the uvs are interpolated from the last three texture coordinates
+        let rec = hit_at(&meshes, 0.25, 0.25).unwrap();
+        assert_close(rec.uv, (0.5, 0.5));
+        assert!(hit_at(&meshes, 0.75, 0.75).is_none());
+    }
+
+    #[test]
+    fn faces_with_normals_but_no_uvs() {
+        let obj = format!("{TRIANGLE}vn 1 0 1\nf 2//1 3//1 4//1\n");
+        let meshes = load_source("normals", &obj, None).unwrap();
+
+        let rec = hit_at(&meshes, 0.25, 0.25).unwrap();
+        let expected = vec3(1.0, 0.0, 1.0).normalize();
+        assert!((rec.normal - expected).length() < 1e-5);
+        // without uvs the barycentric coordinates are used
+        assert_close(rec.uv, (0.25, 0.25));
+    }
+
+    #[test]
+    fn faces_with_uvs_but_no_normals() {
+        let obj = format!("{TRIANGLE}vt 0 0\nvt 1 0\nvt 0 1\nf 2/1 3/2 4/3\n");
+        let meshes = load_source("uvs", &obj, None).unwrap();
+
+        let rec = hit_at(&meshes, 0.5, 0.25).unwrap();
+        assert_close(rec.uv, (0.5, 0.25));
+        assert!((rec.normal - vec3(0.0, 0.0, 1.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn polygons_are_fanned_without_slivers() {
+        // the first fan triangle runs along the bottom edge and has no area
+        let obj = "v 0 0 0\nv 1 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nf 1 2 3 4 5\n";
+        let meshes = load_source("fan", obj, None).unwrap();
+
+        assert_eq!(meshes.hittables.len(), 1);
+        for (x, y) in [(1.5, 0.5), (0.5, 1.5), (1.9, 1.9)] {
+            assert!(hit_at(&meshes, x, y).is_some(), "no hit at ({x}, {y})");
+        }
+        assert!(hit_at(&meshes, 2.5, 1.0).is_none());
+
+        let result = load_source("sliver", "v 0 0 0\nv 1 0 0\nv 2 0 0\nf 1 2 3\n", None);
+        assert_eq!(error_line(result).1, "file does not contain any faces");
+    }
+
+    #[test]
+    fn groups_and_materials_split_meshes() {
+        let mtl = "newmtl red\nKd 0.8 0.2 0.2\n";
+        let obj = "mtllib test.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 1 1 0
+g first
+f 1 2 3
+f 2 4 3
+g second
+usemtl red
+f 1 2 3
+o third
+g empty
+f 1 2 4
+";
+        let meshes = load_source("groups", obj, Some(mtl)).unwrap();
+
+        // `second` is split by `usemtl` and `third` by `g` before either gets a face
+        assert_eq!(meshes.hittables.len(), 3);
+    }
+
+    #[test]
+    fn materials_follow_illum_dissolve_and_emission() {
+        let mtl = "newmtl red
+Kd 0.8 0.2 0.2
+newmtl lamp
+Ke 4 4 4
+newmtl faded
+Kd 0.8 0.8 0.8
+d 0.5
+newmtl clear
+Tr 0.25
+newmtl solid
+Kd 0.8 0.8 0.8
+Tr 0
+newmtl glass
+illum 7
+Ni 1.33
+newmtl chrome
+illum 3
+Ks 1 1 1
+Ns 1000
+";
+        let names = ["red", "lamp", "faded", "clear", "solid", "glass", "chrome"];
+
+        // one triangle per material, three units apart along x
+        let mut obj = String::from("mtllib test.mtl\n");
+        for (k, name) in names.iter().enumerate() {
+            let x = 3 * k;
+            obj += &format!("v {x} 0 0\nv {} 0 0\nv {x} 1 0\n", x + 1);
+            obj += &format!("usemtl {name}\nf -3 -2 -1\n");
+        }
+        let meshes = load_source("materials", &obj, Some(mtl)).unwrap();
+        assert_eq!(meshes.hittables.len(), names.len());
+
+        let up = vec3(0.0, 0.0, 1.0);
+        let material = |k: usize| {
+            let rec = hit_at(&meshes, 3.0 * k as f32 + 0.25, 0.25).unwrap();
+            let ray = Ray::new(vec3(0.0, 0.0, 1.0), vec3(0.0, 0.0, -1.0), 0.0);
+            let emitted = rec.material.emitted(rec.uv, &rec.point);
+            let eval = rec.material.eval(&ray, &rec, up);
+            let pdf = rec.material.pdf(&ray, &rec, up);
+            (emitted, eval, pdf)
+        };
+
+        let (_, red, _) = material(0);
+        let red = red.unwrap() * PI;
+        assert!((red - vec3(0.8, 0.2, 0.2)).length() < 1e-5);
+
+        let (lamp, _, _) = material(1);
+        assert!((lamp - vec3(4.0, 4.0, 4.0)).length() < 1e-5);
+
+        // partial dissolve and the glass illumination models become dielectrics
+        for k in [2, 3, 5] {
+            assert!(
+                material(k).1.is_none(),
+                "`{}` is not a dielectric",
+                names[k]
+            );
+        }
+        // `Tr` is the complement of `d`, so zero transparency stays opaque
+        assert!(material(4).1.is_some());
+
+        // the sharp phong lobe of the chrome is far denser than a diffuse one
+        let (_, chrome, pdf) = material(6);
+        assert!(chrome.is_some() && pdf > 10.0);
+    }
+
+    #[test]
+    fn bad_indices_report_their_line() {
+        let cases = [
+            ("f 1 2 5\n", "index `5` out of range"),
+            ("f 1 2 -5\n", "index `-5` out of range"),
+            ("f 0 1 2\n", "indices start at 1"),
+            ("f 1/4 2 3\n", "index `4` out of range"),
+            ("f 1 2 x\n", "invalid index `x`"),
+        ];
+
+        for (k, (face, message)) in cases.iter().enumerate() {
+            let obj = format!("v 0 0 0\nv 1 0 0\n\nv 0 1 0\nvt 0 0\n{face}");
+            let result = load_source(&format!("index{k}"), &obj, None);
+            assert_eq!(error_line(result), (6, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn unknown_material_reports_its_line() {
+        let mtl = "newmtl red\nKd 0.8 0.2 0.2\n";
+        let obj = "mtllib test.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\nusemtl blue\n";
+
+        let result = load_source("usemtl", obj, Some(mtl));
+        assert_eq!(
+            error_line(result),
+            (7, "unknown material `blue`".to_string())
+        );
+    }
+
+    #[test]
+    fn mtl_errors_report_their_line() {
+        let mtl = "# materials\nKd 0.8 0.2 0.2\n";
+        let obj = "mtllib test.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+
+        let result = load_source("mtl", obj, Some(mtl));
+        assert_eq!(
+            error_line(result),
+            (2, "`Kd` before any `newmtl`".to_string())
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::error::Error;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::{Isotropic, Material};
+use crate::math::{vec3, Ray, Vec3, INFINITY};
+use crate::sampler::Sampler;
+use crate::texture::Texture;
+
+// volume of uniform density filling a convex boundary shape
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f32,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f32,
+        texture: Box<dyn Texture>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            boundary,
+            neg_inv_density: neg_inv_density(density)?,
+            phase_function: Arc::new(Isotropic::new(texture)),
+        })
+    }
+
+    pub fn solid(boundary: Box<dyn Hittable>, density: f32, albedo: Vec3) -> Result<Self, Error> {
+        Ok(Self {
+            boundary,
+            neg_inv_density: neg_inv_density(density)?,
+            phase_function: Arc::new(Isotropic::solid(albedo)),
+        })
+    }
+}
+
+// an empty medium would give nan distances
+fn neg_inv_density(density: f32) -> Result<f32, Error> {
+    if !(density.is_finite() && density > 0.0) {
+        return Err(Error::InvalidInput(format!(
+            "medium density must be positive and finite, got {density}"
+        )));
+    }
+
+    Ok(-1.0 / density)
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        // find where the ray enters and leaves the boundary, even if it starts inside
+        let rec1 = self.boundary.hit(ray, -INFINITY, INFINITY, sampler)?;
+        let rec2 = self.boundary.hit(ray, rec1.t + 0.0001, INFINITY, sampler)?;
+
+        let t_enter = rec1.t.max(ray_tmin).max(0.0);
+        let t_exit = rec2.t.min(ray_tmax);
+
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = ray.direction.length();
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
+        // free flight distance, one minus the sample keeps the log finite
+        let hit_distance = self.neg_inv_density * (1.0 - sampler.get_1d()).ln();
+
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+
+        // normal and facing are meaningless inside a volume
+        let mut rec = HitRecord::new(
+            ray.at(t),
+            vec3(1.0, 0.0, 0.0),
+            ray,
+            t,
+            Arc::clone(&self.phase_function),
+        );
+        rec.normal = vec3(1.0, 0.0, 0.0);
+        rec.front_face = true;
+
+        Some(rec)
+    }
+
+    fn aabb(&self) -> Aabb {
+        self.boundary.aabb()
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{planar_pdf, HitRecord, Hittable};
+use crate::material::Material;
+use crate::math::{sample_unit_disk, vec3, Onb, Ray, Vec3, INFINITY, PI};
+use crate::sampler::Sampler;
+
+pub struct Disk {
+    center: Vec3,
+    radius: f32,
+    basis: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            center,
+            radius,
+            basis: Onb::new(normal),
+            material,
+        }
+    }
+
+    fn intersect(&self, ray: &Ray, ray_tmin: f32, ray_tmax: f32) -> Option<HitRecord> {
+        let normal = self.basis.w;
+        let denom = normal.dot(ray.direction);
+
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.center - ray.origin).dot(normal) / denom;
+        if t <= ray_tmin || ray_tmax <= t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.center;
+        let (x, y) = (offset.dot(self.basis.u), offset.dot(self.basis.v));
+        let r2 = x * x + y * y;
+
+        if r2 > self.radius * self.radius {
+            return None;
+        }
+
+        let mut rec = HitRecord::new(point, normal, ray, t, Arc::clone(&self.material));
+        // u goes around the rim, v outwards from the center
+        rec.uv = (
+            (y.atan2(x) / (2.0 * PI)).rem_euclid(1.0),
+            r2.sqrt() / self.radius,
+        );
+
+        Some(rec)
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        self.intersect(ray, ray_tmin, ray_tmax)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        let area = PI * self.radius * self.radius;
+
+        self.intersect(&Ray::new(origin, direction, 0.0), 0.001, INFINITY)
+            .map_or(0.0, |rec| planar_pdf(&rec, direction, area))
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let p = sample_unit_disk(sampler.get_2d()) * self.radius;
+        self.center + self.basis.u * p.x + self.basis.v * p.y - origin
+    }
+
+    fn aabb(&self) -> Aabb {
+        // extent of a circle along each axis is radius * sqrt(1 - n_axis^2)
+        let n = self.basis.w;
+        let extent = vec3(
+            (1.0 - n.x * n.x).max(0.0).sqrt(),
+            (1.0 - n.y * n.y).max(0.0).sqrt(),
+            (1.0 - n.z * n.z).max(0.0).sqrt(),
+        ) * self.radius;
+
+        Aabb::new(self.center - extent, self.center + extent).padded()
+    }
+}
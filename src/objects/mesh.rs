@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bvh::BVHNode;
+use crate::error::Error;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::math::{Ray, Vec3};
+use crate::objects::triangle::{intersect, is_degenerate};
+use crate::sampler::Sampler;
+
+// vertex attributes are indexed by the same per-face indices as the positions
+struct MeshData {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f32, f32)>>,
+    indices: Vec<[u32; 3]>,
+    material: Arc<dyn Material>,
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    face: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vec3; 3] {
+        let [i0, i1, i2] = self.mesh.indices[self.face];
+        let positions = &self.mesh.positions;
+
+        [
+            positions[i0 as usize],
+            positions[i1 as usize],
+            positions[i2 as usize],
+        ]
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let [p0, p1, p2] = self.vertices();
+        let (t, u, v) = intersect(ray, p0, p1, p2, ray_tmin, ray_tmax)?;
+        let w = 1.0 - u - v;
+
+        let [i0, i1, i2] = self.mesh.indices[self.face].map(|i| i as usize);
+        let geometric_normal = (p1 - p0).cross(p2 - p0).normalize();
+
+        let mut rec = HitRecord::new(
+            ray.at(t),
+            geometric_normal,
+            ray,
+            t,
+            Arc::clone(&self.mesh.material),
+        );
+
+        if let Some(normals) = &self.mesh.normals {
+            let mut shading_normal =
+                (normals[i0] * w + normals[i1] * u + normals[i2] * v).normalize();
+
+            // keep the interpolated normal on the same side as the actual surface
+            if shading_normal.dot(geometric_normal) < 0.0 {
+                shading_normal = -shading_normal;
+            }
+
+            rec.normal = if rec.front_face {
+                shading_normal
+            } else {
+                -shading_normal
+            };
+        }
+
+        rec.uv = match &self.mesh.uvs {
+            Some(uvs) => (
+                uvs[i0].0 * w + uvs[i1].0 * u + uvs[i2].0 * v,
+                uvs[i0].1 * w + uvs[i1].1 * u + uvs[i2].1 * v,
+            ),
+            None => (u, v),
+        };
+
+        Some(rec)
+    }
+
+    fn aabb(&self) -> Aabb {
+        let [p0, p1, p2] = self.vertices();
+
+        Aabb::new(p0, p0)
+            .join(&Aabb::new(p1, p1))
+            .join(&Aabb::new(p2, p2))
+            .padded()
+    }
+}
+
+pub struct Mesh {
+    bvh: BVHNode,
+}
+
+impl Mesh {
+    pub fn new(
+        positions: Vec<Vec3>,
+        normals: Option<Vec<Vec3>>,
+        uvs: Option<Vec<(f32, f32)>>,
+        indices: Vec<[u32; 3]>,
+        material: Arc<dyn Material>,
+    ) -> Result<Self, Error> {
+        if indices
+            .iter()
+            .flatten()
+            .any(|&i| i as usize >= positions.len())
+        {
+            return Err(Error::InvalidInput("mesh index out of range".to_string()));
+        }
+        if indices.iter().any(|face| {
+            let [p0, p1, p2] = face.map(|i| positions[i as usize]);
+            is_degenerate(p0, p1, p2)
+        }) {
+            return Err(Error::InvalidInput(
+                "mesh triangle vertices must not be collinear".to_string(),
+            ));
+        }
+        if normals.as_ref().is_some_and(|n| n.len() != positions.len())
+            || uvs.as_ref().is_some_and(|uv| uv.len() != positions.len())
+        {
+            return Err(Error::InvalidInput(
+                "mesh normals and uvs need one entry per position".to_string(),
+            ));
+        }
+
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            indices,
+            material,
+        });
+
+        let mut triangles: Vec<Box<dyn Hittable>> = (0..mesh.indices.len())
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    mesh: Arc::clone(&mesh),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect();
+
+        Ok(Self {
+            bvh: BVHNode::new(&mut triangles),
+        })
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        self.bvh.hit(ray, ray_tmin, ray_tmax, sampler)
+    }
+
+    fn aabb(&self) -> Aabb {
+        self.bvh.aabb()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::math::vec3;
+
+    fn mesh(indices: Vec<[u32; 3]>, normals: Option<Vec<Vec3>>) -> Result<Mesh, Error> {
+        Mesh::new(
+            vec![
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+            ],
+            normals,
+            None,
+            indices,
+            Arc::new(Lambertian::solid(vec3(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn rejects_invalid_meshes() {
+        assert!(mesh(vec![[0, 1, 2]], None).is_ok());
+        assert!(mesh(vec![[0, 1, 3]], None).is_err());
+        assert!(mesh(vec![[0, 1, 1]], None).is_err());
+        assert!(mesh(vec![[0, 1, 2]], Some(vec![vec3(0.0, 0.0, 1.0)])).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+pub mod constant_medium;
+pub mod disk;
+pub mod mesh;
+pub mod plane;
+pub mod quad;
+pub mod sphere;
+pub mod transformed;
+pub mod triangle;
+
+pub use {
+    constant_medium::ConstantMedium,
+    disk::Disk,
+    mesh::Mesh,
+    plane::Plane,
+    quad::{make_box, Quad},
+    sphere::Sphere,
+    transformed::Transformed,
+    triangle::Triangle,
+};
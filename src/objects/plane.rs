@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::math::{Onb, Ray, Vec3, INFINITY};
+use crate::sampler::Sampler;
+
+// infinite plane, uvs are the unbounded planar coordinates so textures tile across it
+pub struct Plane {
+    point: Vec3,
+    basis: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        Self {
+            point,
+            basis: Onb::new(normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        let normal = self.basis.w;
+        let denom = normal.dot(ray.direction);
+
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.point - ray.origin).dot(normal) / denom;
+        if t <= ray_tmin || ray_tmax <= t {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.point;
+
+        let mut rec = HitRecord::new(point, normal, ray, t, Arc::clone(&self.material));
+        rec.uv = (offset.dot(self.basis.u), offset.dot(self.basis.v));
+
+        Some(rec)
+    }
+
+    fn aabb(&self) -> Aabb {
+        let n = self.basis.w;
+        let mut min = Vec3::from_v(-INFINITY);
+        let mut max = Vec3::from_v(INFINITY);
+
+        // only planes facing along an axis are bounded, and only along that axis
+        if n.y.abs() < 1e-6 && n.z.abs() < 1e-6 {
+            (min.x, max.x) = (self.point.x, self.point.x);
+        } else if n.x.abs() < 1e-6 && n.z.abs() < 1e-6 {
+            (min.y, max.y) = (self.point.y, self.point.y);
+        } else if n.x.abs() < 1e-6 && n.y.abs() < 1e-6 {
+            (min.z, max.z) = (self.point.z, self.point.z);
+        }
+
+        Aabb::new(min, max).padded()
+    }
+}
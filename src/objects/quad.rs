@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{planar_pdf, HitRecord, Hittable, HittableList};
+use crate::material::Material;
+use crate::math::{vec3, Ray, Vec3, INFINITY};
+use crate::sampler::Sampler;
+
+// parallelogram spanned by two edges from a corner
+pub struct Quad {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    d: f32,
+    area: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(origin: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = u.cross(v);
+        let normal = n.normalize();
+
+        Self {
+            origin,
+            u,
+            v,
+            w: n / n.dot(n),
+            normal,
+            d: normal.dot(origin),
+            area: n.length(),
+            material,
+        }
+    }
+
+    fn intersect(&self, ray: &Ray, ray_tmin: f32, ray_tmax: f32) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction);
+
+        // the ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin)) / denom;
+        if t <= ray_tmin || ray_tmax <= t {
+            return None;
+        }
+
+        // planar coordinates of the hit point relative to the two edges
+        let point = ray.at(t);
+        let planar = point - self.origin;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let mut rec = HitRecord::new(point, self.normal, ray, t, Arc::clone(&self.material));
+        rec.uv = (alpha, beta);
+
+        Some(rec)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        self.intersect(ray, ray_tmin, ray_tmax)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        self.intersect(&Ray::new(origin, direction, 0.0), 0.001, INFINITY)
+            .map_or(0.0, |rec| planar_pdf(&rec, direction, self.area))
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let (a, b) = sampler.get_2d();
+        self.origin + self.u * a + self.v * b - origin
+    }
+
+    fn aabb(&self) -> Aabb {
+        let diagonal1 = Aabb::from_points(self.origin, self.origin + self.u + self.v);
+        let diagonal2 = Aabb::from_points(self.origin + self.u, self.origin + self.v);
+
+        diagonal1.join(&diagonal2).padded()
+    }
+}
+
+// axis-aligned box with opposite corners a and b
+pub fn make_box(a: Vec3, b: Vec3, material: Arc<dyn Material>) -> HittableList {
+    let min = vec3(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = vec3(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+    let dx = vec3(max.x - min.x, 0.0, 0.0);
+    let dy = vec3(0.0, max.y - min.y, 0.0);
+    let dz = vec3(0.0, 0.0, max.z - min.z);
+
+    let sides: [(Vec3, Vec3, Vec3); 6] = [
+        (vec3(min.x, min.y, max.z), dx, dy),  // front
+        (vec3(max.x, min.y, max.z), -dz, dy), // right
+        (vec3(max.x, min.y, min.z), -dx, dy), // back
+        (vec3(min.x, min.y, min.z), dz, dy),  // left
+        (vec3(min.x, max.y, max.z), dx, -dz), // top
+        (vec3(min.x, min.y, min.z), dx, dz),  // bottom
+    ];
+
+    HittableList::new(
+        sides
+            .into_iter()
+            .map(|(origin, u, v)| {
+                Box::new(Quad::new(origin, u, v, Arc::clone(&material))) as Box<dyn Hittable>
+            })
+            .collect(),
+    )
+}
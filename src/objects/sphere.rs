@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::math::{sample_unit_sphere, vec3, Onb, Ray, Vec3, INFINITY, PI};
+use crate::sampler::Sampler;
+
+pub struct Sphere {
+    center: Ray,
+    radius: f32,
+    material: Arc<dyn Material>,
+    seam: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            center: Ray::new(center, Vec3::ZEROS, 0.0),
+            radius,
+            material,
+            seam: 0.0,
+        }
+    }
+
+    pub fn moving(center1: Vec3, center2: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            center: Ray::new(center1, center2 - center1, 0.0),
+            radius,
+            material,
+            seam: 0.0,
+        }
+    }
+
+    // rotates the texture seam around the y axis, angle in radians
+    pub fn with_seam(mut self, seam: f32) -> Self {
+        self.seam = seam;
+        self
+    }
+
+    // u runs around the y axis starting at -x, v runs from the bottom pole to the top one
+    fn uv(&self, outward_normal: Vec3) -> (f32, f32) {
+        let theta = (-outward_normal.y).clamp(-1.0, 1.0).acos();
+        let phi = (-outward_normal.z).atan2(outward_normal.x) + PI;
+
+        (((phi + self.seam) / (2.0 * PI)).rem_euclid(1.0), theta / PI)
+    }
+
+    fn intersect(&self, ray: &Ray, ray_tmin: f32, ray_tmax: f32) -> Option<HitRecord> {
+        let current_center = self.center.at(ray.time);
+        let oc = current_center - ray.origin;
+
+        let a = ray.direction.length_squared();
+        let h = ray.direction.dot(oc);
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = h * h - a * c;
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrtd = discriminant.sqrt();
+
+        // find the nearest root that lies in the acceptable range
+        let mut root = (h - sqrtd) / a;
+        if root <= ray_tmin || ray_tmax <= root {
+            root = (h + sqrtd) / a;
+            if root <= ray_tmin || ray_tmax <= root {
+                return None;
+            }
+        }
+
+        let point = ray.at(root);
+        let outward_normal = (point - current_center) / self.radius;
+
+        let mut rec = HitRecord::new(point, outward_normal, ray, root, Arc::clone(&self.material));
+        rec.uv = self.uv(outward_normal);
+
+        Some(rec)
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        self.intersect(ray, ray_tmin, ray_tmax)
+    }
+
+    // lights are sampled where moving spheres are at time zero
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        if self
+            .intersect(&Ray::new(origin, direction, 0.0), 0.001, INFINITY)
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        let distance_squared = (self.center.at(0.0) - origin).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * PI);
+        }
+
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared)
+            .max(0.0)
+            .sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+
+        1.0 / solid_angle
+    }
+
+    // uniform over the cone of directions the sphere covers as seen from the origin
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let direction = self.center.at(0.0) - origin;
+        let distance_squared = direction.length_squared();
+        // from inside the sphere covers every direction
+        if distance_squared <= self.radius * self.radius {
+            return sample_unit_sphere(sampler.get_2d());
+        }
+
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared)
+            .max(0.0)
+            .sqrt();
+
+        let (r1, r2) = sampler.get_2d();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+
+        Onb::new(direction).transform(vec3(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
+
+    fn aabb(&self) -> Aabb {
+        let box1 = Aabb::new(
+            self.center.at(0.0) - self.radius,
+            self.center.at(0.0) + self.radius,
+        );
+        let box2 = Aabb::new(
+            self.center.at(1.0) - self.radius,
+            self.center.at(1.0) + self.radius,
+        );
+
+        box1.join(&box2)
+    }
+}
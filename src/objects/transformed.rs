@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::error::Error;
+use crate::hittable::{HitRecord, Hittable};
+use crate::math::{Mat4, Ray, Vec3};
+use crate::sampler::Sampler;
+
+// places a shared object in the world through an affine transform
+pub struct Transformed {
+    object: Arc<dyn Hittable>,
+    to_world: Mat4,
+    to_object: Mat4,
+    normal_to_world: Mat4,
+    aabb: Aabb,
+}
+
+impl Transformed {
+    pub fn new(object: impl Into<Arc<dyn Hittable>>, transform: Mat4) -> Result<Self, Error> {
+        let object = object.into();
+        let Some(to_object) = transform.inverse() else {
+            return Err(Error::InvalidInput(
+                "instance transform is not invertible".to_string(),
+            ));
+        };
+
+        let aabb = transform_aabb(&transform, &object.aabb());
+
+        Ok(Self {
+            object,
+            to_world: transform,
+            to_object,
+            normal_to_world: to_object.transpose(),
+            aabb,
+        })
+    }
+}
+
+// transforms a box by its extents along each axis, which is the same as
+// transforming its eight corners but copes with unbounded boxes
+fn transform_aabb(m: &Mat4, aabb: &Aabb) -> Aabb {
+    let mut min = [0.0; 3];
+    let mut max = [0.0; 3];
+
+    for i in 0..3 {
+        min[i] = m.m[i][3];
+        max[i] = m.m[i][3];
+
+        for j in 0..3 {
+            // skipping zero entries avoids 0 * inf for infinite planes
+            if m.m[i][j] == 0.0 {
+                continue;
+            }
+
+            let a = m.m[i][j] * aabb.min[j];
+            let b = m.m[i][j] * aabb.max[j];
+            min[i] += a.min(b);
+            max[i] += a.max(b);
+        }
+    }
+
+    Aabb::new(Vec3::from(min), Vec3::from(max))
+}
+
+impl Hittable for Transformed {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        // the direction is not renormalized so t is the same in both spaces
+        let object_ray = Ray::new(
+            self.to_object.transform_point(ray.origin),
+            self.to_object.transform_vector(ray.direction),
+            ray.time,
+        );
+
+        let mut rec = self.object.hit(&object_ray, ray_tmin, ray_tmax, sampler)?;
+
+        rec.point = self.to_world.transform_point(rec.point);
+        rec.normal = self
+            .normal_to_world
+            .transform_vector(rec.normal)
+            .normalize();
+
+        Some(rec)
+    }
+
+    fn aabb(&self) -> Aabb {
+        self.aabb
+    }
+}
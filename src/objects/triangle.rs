@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{planar_pdf, HitRecord, Hittable};
+use crate::material::Material;
+use crate::math::{Ray, Vec3, INFINITY};
+use crate::sampler::Sampler;
+
+// collinear vertices span no surface and have no normal
+pub fn is_degenerate(p0: Vec3, p1: Vec3, p2: Vec3) -> bool {
+    (p1 - p0).cross(p2 - p0).length_squared() == 0.0
+}
+
+// möller-trumbore, returns the ray parameter and the barycentric coordinates of p1 and p2
+pub(super) fn intersect(
+    ray: &Ray,
+    p0: Vec3,
+    p1: Vec3,
+    p2: Vec3,
+    ray_tmin: f32,
+    ray_tmax: f32,
+) -> Option<(f32, f32, f32)> {
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+
+    let pvec = ray.direction.cross(edge2);
+    let det = edge1.dot(pvec);
+
+    // the ray is parallel to the triangle. det scales with the edges and the direction,
+    // so it is compared against their lengths to keep tiny triangles
+    let scale = edge1.length() * edge2.length() * ray.direction.length();
+    if det.abs() <= scale * f32::EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    if !inv_det.is_finite() {
+        return None;
+    }
+    let tvec = ray.origin - p0;
+
+    let u = tvec.dot(pvec) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let qvec = tvec.cross(edge1);
+    let v = ray.direction.dot(qvec) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(qvec) * inv_det;
+    if t <= ray_tmin || ray_tmax <= t {
+        return None;
+    }
+
+    Some((t, u, v))
+}
+
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    normal: Vec3,
+    area: f32,
+    material: Arc<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(p0: Vec3, p1: Vec3, p2: Vec3, material: Arc<dyn Material>) -> Self {
+        Self {
+            vertices: [p0, p1, p2],
+            normal: (p1 - p0).cross(p2 - p0).normalize(),
+            area: 0.5 * (p1 - p0).cross(p2 - p0).length(),
+            material,
+        }
+    }
+
+    fn intersect(&self, ray: &Ray, ray_tmin: f32, ray_tmax: f32) -> Option<HitRecord> {
+        let [p0, p1, p2] = self.vertices;
+        let (t, u, v) = intersect(ray, p0, p1, p2, ray_tmin, ray_tmax)?;
+
+        let mut rec = HitRecord::new(ray.at(t), self.normal, ray, t, Arc::clone(&self.material));
+        rec.uv = (u, v);
+
+        Some(rec)
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        ray_tmin: f32,
+        ray_tmax: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord> {
+        self.intersect(ray, ray_tmin, ray_tmax)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        self.intersect(&Ray::new(origin, direction, 0.0), 0.001, INFINITY)
+            .map_or(0.0, |rec| planar_pdf(&rec, direction, self.area))
+    }
+
+    // uniform barycentric coordinates from a square sample
+    fn random(&self, origin: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let [p0, p1, p2] = self.vertices;
+        let (a, b) = sampler.get_2d();
+        let s = a.sqrt();
+
+        p0 * (1.0 - s) + p1 * (s * (1.0 - b)) + p2 * (s * b) - origin
+    }
+
+    fn aabb(&self) -> Aabb {
+        let [p0, p1, p2] = self.vertices;
+
+        Aabb::new(p0, p0)
+            .join(&Aabb::new(p1, p1))
+            .join(&Aabb::new(p2, p2))
+            .padded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::vec3;
+
+    #[test]
+    fn tiny_triangle_is_hit() {
+        let (p0, p1, p2) = (
+            vec3(-1.5e-4, -1.5e-4, 0.0),
+            vec3(1.5e-4, -1.5e-4, 0.0),
+            vec3(0.0, 1.5e-4, 0.0),
+        );
+        // camera rays aren't normalized, this one reaches the triangle at t = 1
+        let ray = Ray::new(vec3(0.0, 0.0, 9e-4), vec3(0.0, 0.0, -9e-4), 0.0);
+
+        let (t, u, v) = intersect(&ray, p0, p1, p2, 0.0, INFINITY).unwrap();
+        assert!((t - 1.0).abs() < 1e-5);
+        assert!((u - 0.25).abs() < 1e-5 && (v - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn parallel_ray_misses() {
+        let (p0, p1, p2) = (
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(vec3(0.2, 0.2, 0.0), vec3(1.0, 0.0, 0.0), 0.0);
+
+        assert!(intersect(&ray, p0, p1, p2, 0.0, INFINITY).is_none());
+    }
+}
@@ -1,74 +1,632 @@
-use console::Style;
-use indicatif::{ProgressBar, ProgressStyle};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
+use rayon::prelude::*;
+
+use crate::background::Background;
 use crate::camera::Camera;
-use crate::hittable::Hittable;
-use crate::math::{linear_to_gamma, vec3, Ray, Vec3, INFINITY};
+use crate::error::Error;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::math::{linear_to_srgb, luminance, srgb_to_linear, vec3, Ray, Vec3, INFINITY};
+use crate::sampler::{Sampler, SamplerKind};
+use crate::tonemap::DisplayTransform;
+
+// everything a path needs to know about the scene
+struct Integrator<'a, H> {
+    world: &'a H,
+    lights: &'a HittableList,
+    background: &'a Background,
+    max_depth: u32,
+    roulette_depth: u32,
+}
+
+impl<H: Hittable> Integrator<'_, H> {
+    fn ray_color(&self, mut ray: Ray, sampler: &mut dyn Sampler) -> Vec3 {
+        let mut color = Vec3::ZEROS;
+        // product of the attenuations along the path so far
+        let mut throughput = Vec3::ONES;
+        // density the previous bounce picked `ray` with, none if it didn't sample the
+        // lights, so emission found next is weighted against light sampling
+        let mut bsdf_pdf = None;
+
+        for depth in 0..self.max_depth {
+            let Some(rec) = self.world.hit(&ray, 0.001, INFINITY, sampler) else {
+                color += throughput * self.background.value(&ray);
+                break;
+            };
+
+            let mut emitted = rec.material.emitted(rec.uv, &rec.point);
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                if emitted.length_squared() > 0.0 {
+                    let light_pdf = self.lights.pdf_value(ray.origin, ray.direction);
+                    emitted = emitted * power_heuristic(bsdf_pdf, light_pdf);
+                }
+            }
+
+            color += throughput * (emitted + self.sample_lights(&ray, &rec, sampler));
 
-fn ray_color<H: Hittable>(ray: &Ray, world: &H, depth: u32) -> Vec3 {
-    if depth == 0 {
-        return Vec3::ZEROS;
+            let Some(sample) = rec.material.sample(&ray, &rec, sampler) else {
+                break;
+            };
+            throughput = throughput * sample.attenuation;
+
+            // past the minimum depth dark paths are ended at random, and the
+            // survivors are weighted up so the estimate stays unbiased
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.x.max(throughput.y).max(throughput.z).min(1.0);
+                if sampler.get_1d() >= survival {
+                    break;
+                }
+
+                throughput /= survival;
+            }
+
+            ray = sample.scattered;
+            bsdf_pdf = sample.pdf;
+        }
+
+        color
     }
 
-    if let Some(rec) = world.hit(ray, 0.001, INFINITY) {
-        if let Some((attenuation, scattered)) = rec.material.scatter(ray, &rec) {
-            return ray_color(&scattered, world, depth - 1) * attenuation;
+    // light arriving from the direction of a point picked on one of the lights, weighted
+    // against finding it through the material. whatever emitter is closest along that
+    // direction is counted, so emitters outside the lights list get the same mixture pdf
+    // as the material side assumes. mirror-like materials that can't be evaluated for an
+    // arbitrary direction only get light through `sample`
+    fn sample_lights(&self, ray: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Vec3 {
+        if self.lights.hittables.is_empty() {
+            return Vec3::ZEROS;
+        }
+
+        let direction = self.lights.random(rec.point, sampler);
+        let Some(f) = rec.material.eval(ray, rec, direction) else {
+            return Vec3::ZEROS;
+        };
+
+        let light_pdf = self.lights.pdf_value(rec.point, direction);
+        if light_pdf <= 0.0 || f.length_squared() == 0.0 {
+            return Vec3::ZEROS;
         }
 
-        return Vec3::ZEROS;
+        let shadow_ray = Ray::new(rec.point, direction, ray.time);
+        let Some(hit) = self.world.hit(&shadow_ray, 0.001, INFINITY, sampler) else {
+            return Vec3::ZEROS;
+        };
+
+        let emitted = hit.material.emitted(hit.uv, &hit.point);
+        if emitted.length_squared() == 0.0 {
+            return Vec3::ZEROS;
+        }
+
+        let weight = power_heuristic(light_pdf, rec.material.pdf(ray, rec, direction));
+        f * emitted * (weight / light_pdf)
     }
+}
 
-    let unit_direction = ray.direction.normalize();
-    let a = (unit_direction.y + 1.0) * 0.5;
+// weight of a sample taken with density `pdf` when another strategy could have
+// produced it with density `other_pdf`
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b > 0.0 {
+        a / (a + b)
+    } else {
+        0.0
+    }
+}
 
-    Vec3::ONES * (1.0 - a) + vec3(0.5, 0.7, 1.0) * a
+// linear rgb radiance, row by row from the top left
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Vec3>,
+    // number of samples taken for every pixel
+    pub samples: Vec<u32>,
+}
+
+impl Framebuffer {
+    pub fn average_samples(&self) -> f32 {
+        self.samples.iter().map(|&n| n as f64).sum::<f64>() as f32 / self.samples.len() as f32
+    }
+
+    // visualizes the samples spent per pixel, from dark blue for the fewest to red for the most
+    pub fn sample_heatmap(&self) -> Framebuffer {
+        let max = self.samples.iter().copied().max().unwrap_or(0).max(1);
+
+        // the colors are picked srgb encoded and decoded so they come out unchanged on save
+        let pixels = self
+            .samples
+            .iter()
+            .map(|&n| {
+                let color = heatmap_color(n as f32 / max as f32);
+                vec3(
+                    srgb_to_linear(color.x),
+                    srgb_to_linear(color.y),
+                    srgb_to_linear(color.z),
+                )
+            })
+            .collect();
+
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            pixels,
+            samples: self.samples.clone(),
+        }
+    }
+
+    // srgb encoded 8-bit rgb after exposure and tone mapping
+    pub fn to_rgb8(&self, display: &DisplayTransform) -> Vec<u8> {
+        let encode = |c: f32| (255.0 * linear_to_srgb(c) + 0.5) as u8;
+
+        self.pixels
+            .iter()
+            .flat_map(|&color| {
+                let color = display.apply(color);
+                [encode(color.x), encode(color.y), encode(color.z)]
+            })
+            .collect()
+    }
+
+    // the image format is picked from the file extension. exr, hdr and pfm keep the
+    // linear floating point values, everything else goes through `display`
+    pub fn save<P: AsRef<Path>>(&self, path: P, display: &DisplayTransform) -> Result<(), Error> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        let result = match extension.as_deref() {
+            Some("exr" | "hdr") => image::Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+                let color = self.pixels[(y * self.width + x) as usize];
+                image::Rgb([color.x, color.y, color.z])
+            })
+            .save(path),
+            Some("pfm") => self.save_pfm(path).map_err(image::ImageError::IoError),
+            _ => image::save_buffer(
+                path,
+                &self.to_rgb8(display),
+                self.width,
+                self.height,
+                image::ColorType::Rgb8,
+            ),
+        };
+
+        result.map_err(|err| Error::Output(path.to_path_buf(), err))
+    }
+
+    // portable float map, little endian floats with the rows stored bottom to top
+    fn save_pfm(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+
+        for row in self.pixels.chunks(self.width as usize).rev() {
+            for color in row {
+                for c in [color.x, color.y, color.z] {
+                    out.write_all(&c.to_le_bytes())?;
+                }
+            }
+        }
+
+        out.flush()
+    }
+}
+
+fn heatmap_color(t: f32) -> Vec3 {
+    let stops = [
+        vec3(0.05, 0.05, 0.3),
+        vec3(0.1, 0.5, 0.9),
+        vec3(0.2, 0.8, 0.4),
+        vec3(1.0, 0.85, 0.1),
+        vec3(0.9, 0.1, 0.1),
+    ];
+
+    let x = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let k = (x as usize).min(stops.len() - 2);
+    let f = x - k as f32;
+
+    stops[k] * (1.0 - f) + stops[k + 1] * f
+}
+
+// samples pixels in passes and stops once the confidence interval of a pixel's
+// luminance is small enough, both for the pixel and its neighbours
+#[derive(Clone, Copy)]
+pub struct AdaptiveSampling {
+    // samples every pixel gets in the first pass, and per pass after that
+    pub min_samples: u32,
+    // allowed error relative to the pixel's luminance
+    pub noise_threshold: f32,
+}
+
+impl AdaptiveSampling {
+    fn converged(&self, stats: &PixelStats) -> bool {
+        if stats.count < 2 {
+            return false;
+        }
+
+        let variance = stats.m2 / (stats.count - 1) as f32;
+        let error = 1.96 * (variance / stats.count as f32).sqrt();
+
+        // dark pixels would otherwise never converge in relative terms
+        error <= self.noise_threshold * stats.mean.max(0.01)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PixelStats {
+    color: Vec3,
+    count: u32,
+    // running luminance mean and squared deviations, following welford's algorithm
+    mean: f32,
+    m2: f32,
+}
+
+impl PixelStats {
+    const EMPTY: Self = Self {
+        color: Vec3::ZEROS,
+        count: 0,
+        mean: 0.0,
+        m2: 0.0,
+    };
+
+    fn add(&mut self, sample: Vec3) {
+        self.color += sample;
+        self.count += 1;
+
+        let l = luminance(sample);
+        let delta = l - self.mean;
+        self.mean += delta / self.count as f32;
+        self.m2 += delta * (l - self.mean);
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct RenderSettings {
+    pub size: (u32, u32),
+    // the most samples a pixel gets, and the exact count without adaptive sampling
+    pub samples_per_pixel: u32,
+    pub adaptive: Option<AdaptiveSampling>,
+    pub max_depth: u32,
+    // bounces before russian roulette may end a path
+    pub roulette_depth: u32,
+    pub sampler: SamplerKind,
+    // the same seed always produces the same image
+    pub seed: u64,
+}
+
+impl RenderSettings {
+    fn samples_per_pass(&self) -> u32 {
+        match self.adaptive {
+            Some(adaptive) => adaptive.min_samples.clamp(1, self.samples_per_pixel.max(1)),
+            None => self.samples_per_pixel,
+        }
+    }
+
+    // upper bound on the number of passes, adaptive renders may finish earlier
+    pub fn passes(&self) -> u32 {
+        let per_pass = self.samples_per_pass();
+        if per_pass == 0 {
+            return 1;
+        }
+
+        self.samples_per_pixel.div_ceil(per_pass).max(1)
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            size: (400, 225),
+            samples_per_pixel: 100,
+            adaptive: None,
+            max_depth: 50,
+            roulette_depth: 3,
+            sampler: SamplerKind::default(),
+            seed: 0,
+        }
+    }
 }
 
 pub fn render<H: Hittable>(
-    camera: Camera,
-    world: H,
-    size: (u32, u32),
-    samples_per_pixel: u32,
-    max_depth: u32,
-    path: &str,
-) {
-    let pb = ProgressBar::new(size.1 as u64);
-    pb.set_style(
-        ProgressStyle::with_template("{prefix:.cyan.bold} [{bar:25}] {percent_precise}%")
-            .unwrap()
-            .progress_chars("=> "),
-    );
-    pb.set_prefix("Rendering");
+    camera: &Camera,
+    world: &H,
+    lights: &HittableList,
+    background: &Background,
+    settings: &RenderSettings,
+) -> Framebuffer {
+    render_with_progress(camera, world, lights, background, settings, || {})
+}
+
+// `progress` is called from the render threads once for every finished row of every pass
+pub fn render_with_progress<H: Hittable, F: Fn() + Sync>(
+    camera: &Camera,
+    world: &H,
+    lights: &HittableList,
+    background: &Background,
+    settings: &RenderSettings,
+    progress: F,
+) -> Framebuffer {
+    let integrator = Integrator {
+        world,
+        lights,
+        background,
+        max_depth: settings.max_depth,
+        roulette_depth: settings.roulette_depth,
+    };
 
-    let mut buffer = Vec::with_capacity((size.0 * size.1 * 3) as usize);
+    let (width, height) = settings.size;
+    let (w, h) = (width as usize, height as usize);
+
+    // rows can't be split into chunks of zero pixels
+    if w * h == 0 {
+        return Framebuffer {
+            width,
+            height,
+            pixels: Vec::new(),
+            samples: Vec::new(),
+        };
+    }
 
-    for j in 0..size.1 {
-        for i in 0..size.0 {
-            let mut color = Vec3::ZEROS;
-            for _ in 0..samples_per_pixel {
-                color += ray_color(&camera.get_ray(i, j), &world, max_depth)
+    let per_pass = settings.samples_per_pass();
+    let mut stats = vec![PixelStats::EMPTY; w * h];
+    let mut active = vec![true; w * h];
+
+    loop {
+        // each scanline is rendered independently, so rows are spread across the thread pool
+        stats
+            .par_chunks_mut(w)
+            .zip(active.par_chunks(w))
+            .enumerate()
+            .for_each(|(j, (row, row_active))| {
+                let mut sampler = settings
+                    .sampler
+                    .create(settings.samples_per_pixel, settings.seed);
+
+                for (i, pixel) in row.iter_mut().enumerate() {
+                    if !row_active[i] {
+                        continue;
+                    }
+
+                    let (i, j) = (i as u32, j as u32);
+                    let end = (pixel.count + per_pass).min(settings.samples_per_pixel);
+
+                    while pixel.count < end {
+                        sampler.start_pixel_sample((i, j), pixel.count);
+
+                        let ray = camera.get_ray(i, j, sampler.as_mut());
+                        pixel.add(integrator.ray_color(ray, sampler.as_mut()));
+                    }
+                }
+
+                progress();
+            });
+
+        let Some(adaptive) = settings.adaptive else {
+            break;
+        };
+
+        let converged: Vec<bool> = stats
+            .iter()
+            .map(|p| p.count >= settings.samples_per_pixel || adaptive.converged(p))
+            .collect();
+
+        // a pixel keeps going while any of its neighbours is still noisy, so pixels
+        // whose first samples happened to agree don't stop early
+        let mut any_active = false;
+        for j in 0..h {
+            for i in 0..w {
+                let index = j * w + i;
+                let noisy = (j.saturating_sub(1)..(j + 2).min(h))
+                    .any(|y| (i.saturating_sub(1)..(i + 2).min(w)).any(|x| !converged[y * w + x]));
+
+                active[index] = noisy && stats[index].count < settings.samples_per_pixel;
+                any_active |= active[index];
             }
-            color /= samples_per_pixel as f32;
+        }
+
+        if !any_active {
+            break;
+        }
+    }
+
+    let pixels = stats
+        .iter()
+        .map(|p| p.color / p.count.max(1) as f32)
+        .collect();
+
+    Framebuffer {
+        width,
+        height,
+        pixels,
+        samples: stats.iter().map(|p| p.count).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::bvh::BVHNode;
+    use crate::material::{DiffuseLight, Lambertian, Material};
+    use crate::objects::{make_box, Quad, Sphere};
+    use crate::scenes;
+
+    // the smoke scene also draws media distances from the sampler
+    fn render_smoke(threads: usize, sampler: SamplerKind) -> Framebuffer {
+        let size = (24, 24);
+        let scene = scenes::cornell_smoke(size.0, size.1).unwrap();
+        let mut objects = scene.world.hittables;
+        let world = BVHNode::new(&mut objects);
 
-            let rbyte = (255.999 * linear_to_gamma(color.x)) as u8;
-            let gbyte = (255.999 * linear_to_gamma(color.y)) as u8;
-            let bbyte = (255.999 * linear_to_gamma(color.z)) as u8;
+        let settings = RenderSettings {
+            size,
+            samples_per_pixel: 8,
+            adaptive: Some(AdaptiveSampling {
+                min_samples: 2,
+                noise_threshold: 0.05,
+            }),
+            max_depth: 8,
+            sampler,
+            seed: 7,
+            ..RenderSettings::default()
+        };
 
-            buffer.push(rbyte);
-            buffer.push(gbyte);
-            buffer.push(bbyte);
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| {
+                render(
+                    &scene.camera,
+                    &world,
+                    &scene.lights,
+                    &scene.background,
+                    &settings,
+                )
+            })
+    }
+
+    fn assert_same(a: &Framebuffer, b: &Framebuffer) {
+        assert_eq!(a.samples, b.samples);
+        for (i, (p, q)) in a.pixels.iter().zip(&b.pixels).enumerate() {
+            assert_eq!(
+                [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()],
+                [q.x.to_bits(), q.y.to_bits(), q.z.to_bits()],
+                "pixel {i} differs"
+            );
         }
+    }
 
-        pb.inc(1);
+    // mean radiance leaving a white floor below an emissive box, with a sphere light
+    // behind the box that is the only registered light when `sample_lights` is set
+    fn floor_radiance(sample_lights: bool) -> f32 {
+        let light: Arc<dyn Material> = Arc::new(DiffuseLight::solid(vec3(4.0, 4.0, 4.0)));
+        let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(vec3(0.0, 6.0, 0.0), 2.0, light));
+
+        let mut world = HittableList::new(Vec::new());
+        world.add(Box::new(Quad::new(
+            vec3(-50.0, 0.0, -50.0),
+            vec3(100.0, 0.0, 0.0),
+            vec3(0.0, 0.0, 100.0),
+            Arc::new(Lambertian::solid(vec3(0.5, 0.5, 0.5))),
+        )));
+        world.add(Box::new(make_box(
+            vec3(-1.5, 3.0, -1.5),
+            vec3(1.5, 3.5, 1.5),
+            Arc::new(DiffuseLight::solid(vec3(2.0, 2.0, 2.0))),
+        )));
+        world.add(Box::new(Arc::clone(&sphere)));
+
+        let mut lights = HittableList::new(Vec::new());
+        if sample_lights {
+            lights.add(Box::new(sphere));
+        }
+
+        let integrator = Integrator {
+            world: &world,
+            lights: &lights,
+            background: &Background::Solid(Vec3::ZEROS),
+            max_depth: 2,
+            roulette_depth: 2,
+        };
+
+        let samples = 100_000;
+        let mut sampler = SamplerKind::Independent.create(samples, 3);
+        let mut total = 0.0;
+        for index in 0..samples {
+            sampler.start_pixel_sample((0, 0), index);
+            let ray = Ray::new(vec3(0.0, 1.0, 0.0), vec3(0.0, -1.0, 0.0), 0.0);
+            total += luminance(integrator.ray_color(ray, sampler.as_mut()));
+        }
+
+        total / samples as f32
     }
 
-    pb.finish_and_clear();
-    image::save_buffer(path, &buffer, size.0, size.1, image::ColorType::Rgb8).unwrap();
+    #[test]
+    fn unlisted_emitter_keeps_its_energy_under_mis() {
+        let with_lights = floor_radiance(true);
+        let without_lights = floor_radiance(false);
 
-    println!(
-        "{} to `{}` in {:?}",
-        Style::new().green().bold().apply_to("Rendered"),
-        path,
-        pb.elapsed()
-    );
+        assert!(
+            (with_lights - without_lights).abs() < 0.03 * without_lights,
+            "{with_lights} with light sampling, {without_lights} without"
+        );
+    }
+
+    #[test]
+    fn empty_image_renders_nothing() {
+        let scene = scenes::cornell_box(4, 4).unwrap();
+        for size in [(0, 4), (4, 0)] {
+            let settings = RenderSettings {
+                size,
+                ..RenderSettings::default()
+            };
+            let framebuffer = render(
+                &scene.camera,
+                &scene.world,
+                &scene.lights,
+                &scene.background,
+                &settings,
+            );
+
+            assert!(framebuffer.pixels.is_empty() && framebuffer.samples.is_empty());
+        }
+    }
+
+    #[test]
+    fn pfm_round_trip() {
+        let framebuffer = Framebuffer {
+            width: 2,
+            height: 2,
+            pixels: vec![
+                vec3(0.0, 0.25, 0.5),
+                vec3(1.0, 2.0, 3.0),
+                vec3(-1.5, 1e-3, 1e6),
+                vec3(0.125, 7.0, 0.75),
+            ],
+            samples: vec![1; 4],
+        };
+
+        let path = std::env::temp_dir().join(format!("pfm_round_trip_{}.pfm", std::process::id()));
+        framebuffer
+            .save(&path, &DisplayTransform::default())
+            .unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+
+        let values: Vec<f32> = bytes[header.len()..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(values.len(), 12);
+
+        // rows are stored bottom to top, so the file starts with the bottom left pixel
+        assert_eq!(values[..3], [-1.5, 1e-3, 1e6]);
+        let read: Vec<[f32; 3]> = values
+            .chunks_exact(6)
+            .rev()
+            .flat_map(|row| row.chunks_exact(3).map(|c| [c[0], c[1], c[2]]))
+            .collect();
+        let written: Vec<[f32; 3]> = framebuffer.pixels.iter().map(|c| [c.x, c.y, c.z]).collect();
+        assert_eq!(read, written);
+    }
+
+    #[test]
+    fn same_seed_renders_the_same_image() {
+        for sampler in [SamplerKind::Independent, SamplerKind::Sobol] {
+            assert_same(&render_smoke(2, sampler), &render_smoke(2, sampler));
+        }
+    }
+
+    #[test]
+    fn thread_count_does_not_change_the_image() {
+        for sampler in [SamplerKind::Independent, SamplerKind::Sobol] {
+            assert_same(&render_smoke(1, sampler), &render_smoke(4, sampler));
+        }
+    }
 }
@@ -0,0 +1,319 @@
+use crate::math::{hash, mix_bits, Rng};
+
+// largest float below one, samples are kept in [0, 1)
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+// bases of the halton dimensions, deeper dimensions fall back to independent samples
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+// hands out the sample values of one pixel sample, one dimension after another.
+// camera rays always take the pixel, lens and time dimensions first, so those
+// land in the best distributed dimensions of the low-discrepancy sequences
+pub trait Sampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32);
+    fn get_1d(&mut self) -> f32;
+    fn get_2d(&mut self) -> (f32, f32);
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    #[default]
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn create(self, samples_per_pixel: u32, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel, seed)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+// state shared by all samplers: the current pixel sample and the next dimension to hand out
+struct SampleState {
+    seed: u64,
+    pixel_hash: u64,
+    index: u32,
+    dimension: u32,
+    rng: Rng,
+}
+
+impl SampleState {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel_hash: 0,
+            index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    fn start(&mut self, pixel: (u32, u32), index: u32) {
+        self.pixel_hash = hash(&[self.seed, pixel.0 as u64, pixel.1 as u64]);
+        self.index = index;
+        self.dimension = 0;
+        self.rng = Rng::new(hash(&[self.pixel_hash, index as u64]));
+    }
+
+    // hash of the pixel and the dimension, constant over all samples of the pixel
+    fn next_dimension(&mut self, count: u32) -> u64 {
+        let h = mix_bits(self.pixel_hash ^ self.dimension as u64);
+        self.dimension += count;
+        h
+    }
+}
+
+// uniform random samples with no correlation between them
+pub struct IndependentSampler {
+    state: SampleState,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: SampleState::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        self.state.rng.random()
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        (self.state.rng.random(), self.state.rng.random())
+    }
+}
+
+// one jittered sample per stratum, with the strata visited in a different order in every dimension
+pub struct StratifiedSampler {
+    state: SampleState,
+    strata: u32,
+    strata_2d: (u32, u32),
+}
+
+impl StratifiedSampler {
+    pub fn new(samples_per_pixel: u32, seed: u64) -> Self {
+        let strata = samples_per_pixel.max(1);
+        let x = (strata as f32).sqrt().ceil() as u32;
+
+        Self {
+            state: SampleState::new(seed),
+            strata,
+            strata_2d: (x, strata.div_ceil(x)),
+        }
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let h = self.state.next_dimension(1) as u32;
+        let stratum = permutation_element(self.state.index % self.strata, self.strata, h);
+
+        ((stratum as f32 + self.state.rng.random()) / self.strata as f32).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        let h = self.state.next_dimension(2) as u32;
+        let (nx, ny) = self.strata_2d;
+        let stratum = permutation_element(self.state.index % (nx * ny), nx * ny, h);
+
+        let x = (stratum % nx) as f32 + self.state.rng.random();
+        let y = (stratum / nx) as f32 + self.state.rng.random();
+
+        (
+            (x / nx as f32).min(ONE_MINUS_EPSILON),
+            (y / ny as f32).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+// halton sequence with its digits owen scrambled per pixel
+pub struct HaltonSampler {
+    state: SampleState,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: SampleState::new(seed),
+        }
+    }
+
+    fn sample(&mut self) -> f32 {
+        let dimension = self.state.dimension as usize;
+        let h = self.state.next_dimension(1);
+
+        match PRIMES.get(dimension) {
+            Some(&base) => owen_scrambled_radical_inverse(base, self.state.index, h),
+            None => self.state.rng.random(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        self.sample()
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        (self.sample(), self.sample())
+    }
+}
+
+// padded sobol: every 1d or 2d request uses the first sobol dimensions with
+// its own shuffle of the sample index and its own owen scrambling
+pub struct SobolSampler {
+    state: SampleState,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: SampleState::new(seed),
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel: (u32, u32), index: u32) {
+        self.state.start(pixel, index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let h = self.state.next_dimension(1);
+        let index = nested_uniform_scramble(self.state.index, h as u32);
+
+        to_unit_float(nested_uniform_scramble(sobol(index, 0), mix_bits(h) as u32))
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        let h = self.state.next_dimension(2);
+        let index = nested_uniform_scramble(self.state.index, h as u32);
+
+        (
+            to_unit_float(nested_uniform_scramble(sobol(index, 0), (h >> 32) as u32)),
+            to_unit_float(nested_uniform_scramble(sobol(index, 1), mix_bits(h) as u32)),
+        )
+    }
+}
+
+fn to_unit_float(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1u32 << 24) as f32
+}
+
+// the first two sobol dimensions, the van der corput sequence and its pascal matrix companion
+fn sobol(mut index: u32, dimension: usize) -> u32 {
+    if dimension == 0 {
+        return index.reverse_bits();
+    }
+
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= direction;
+        }
+
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+
+    result
+}
+
+// hash based owen scrambling from burley's "practical hash-based owen scrambling"
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+fn owen_scrambled_radical_inverse(base: u32, mut index: u32, hash: u64) -> f32 {
+    let inv_base = 1.0 / base as f32;
+    let mut inv_base_m = 1.0;
+    let mut reversed_digits: u64 = 0;
+
+    // every digit is permuted depending on the digits before it
+    while 1.0 - inv_base_m < 1.0 {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_hash = mix_bits(hash ^ reversed_digits) as u32;
+
+        reversed_digits =
+            reversed_digits * base as u64 + permutation_element(digit, base, digit_hash) as u64;
+        inv_base_m *= inv_base;
+        index = next;
+    }
+
+    (reversed_digits as f32 * inv_base_m).min(ONE_MINUS_EPSILON)
+}
+
+// element `i` of a random permutation of 0..len, from kensler's "correlated multi-jittered sampling"
+fn permutation_element(mut i: u32, len: u32, p: u32) -> u32 {
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+
+        if i < len {
+            break;
+        }
+    }
+
+    (i.wrapping_add(p)) % len
+}
@@ -0,0 +1,765 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::background::{Background, EnvironmentMap};
+use crate::bvh::BVHNode;
+use crate::camera::Camera;
+use crate::hittable::{Hittable, HittableList};
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::math::{degrees_to_radians, vec3, Mat4, Vec3};
+use crate::obj;
+use crate::objects::{
+    make_box, triangle::is_degenerate, ConstantMedium, Disk, Mesh, Plane, Quad, Sphere,
+    Transformed, Triangle,
+};
+use crate::scenes::Scene;
+use crate::texture::{
+    Checkered, ImageTexture, Marble, Noise, Solid, Texture, Turbulence, Wood, Wrap,
+};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            // toml errors already carry the line, column and a snippet of the source
+            SceneError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            SceneError::Invalid {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    camera: CameraDesc,
+    #[serde(default)]
+    background: Option<Spanned<BackgroundDesc>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDesc>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDesc>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    look_from: [f32; 3],
+    look_at: [f32; 3],
+    #[serde(default = "default_vup")]
+    vup: [f32; 3],
+    // angles are given in degrees
+    vfov: f32,
+    #[serde(default)]
+    defocus_angle: f32,
+    #[serde(default)]
+    focus_dist: Option<f32>,
+}
+
+fn default_vup() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDesc {
+    Solid { color: [f32; 3] },
+    Gradient { bottom: [f32; 3], top: [f32; 3] },
+    Sky,
+    Environment { path: String },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: Option<[f32; 3]>,
+        texture: Option<TextureDesc>,
+    },
+    Metal {
+        albedo: [f32; 3],
+        #[serde(default)]
+        fuzz: f32,
+    },
+    Dielectric {
+        refraction_index: f32,
+    },
+    DiffuseLight {
+        emit: Option<[f32; 3]>,
+        texture: Option<TextureDesc>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        albedo: [f32; 3],
+    },
+    Checkered {
+        scale: f32,
+        even: Box<TextureDesc>,
+        odd: Box<TextureDesc>,
+    },
+    Noise {
+        #[serde(default)]
+        seed: u64,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default = "black")]
+        low: [f32; 3],
+        #[serde(default = "white")]
+        high: [f32; 3],
+    },
+    Turbulence {
+        #[serde(default)]
+        seed: u64,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default = "black")]
+        low: [f32; 3],
+        #[serde(default = "white")]
+        high: [f32; 3],
+    },
+    Marble {
+        #[serde(default)]
+        seed: u64,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default = "white")]
+        base: [f32; 3],
+        #[serde(default = "black")]
+        vein: [f32; 3],
+    },
+    Wood {
+        #[serde(default)]
+        seed: u64,
+        #[serde(default = "default_scale")]
+        scale: f32,
+        light: [f32; 3],
+        dark: [f32; 3],
+    },
+    Image {
+        path: String,
+        #[serde(default)]
+        wrap: WrapDesc,
+    },
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn black() -> [f32; 3] {
+    [0.0, 0.0, 0.0]
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum WrapDesc {
+    #[default]
+    Repeat,
+    Clamp,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: String,
+        // degrees
+        #[serde(default)]
+        seam: f32,
+    },
+    MovingSphere {
+        center1: [f32; 3],
+        center2: [f32; 3],
+        radius: f32,
+        material: String,
+        #[serde(default)]
+        seam: f32,
+    },
+    Triangle {
+        vertices: [[f32; 3]; 3],
+        material: String,
+    },
+    Quad {
+        origin: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+    },
+    Disk {
+        center: [f32; 3],
+        normal: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+        material: String,
+    },
+    Box {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: String,
+    },
+    Obj {
+        path: String,
+        #[serde(default)]
+        material: Option<String>,
+    },
+    ConstantMedium {
+        boundary: Box<ObjectDesc>,
+        density: f32,
+        albedo: Option<[f32; 3]>,
+        texture: Option<TextureDesc>,
+    },
+    Instance {
+        object: Box<ObjectDesc>,
+        // applied in the order given
+        transform: Vec<TransformDesc>,
+    },
+    Mesh {
+        positions: Vec<[f32; 3]>,
+        #[serde(default)]
+        normals: Option<Vec<[f32; 3]>>,
+        #[serde(default)]
+        uvs: Option<Vec<[f32; 2]>>,
+        indices: Vec<[u32; 3]>,
+        material: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDesc {
+    Translate([f32; 3]),
+    Scale([f32; 3]),
+    // angles are given in degrees
+    RotateX(f32),
+    RotateY(f32),
+    RotateZ(f32),
+    Rotate { axis: [f32; 3], angle: f32 },
+}
+
+impl TransformDesc {
+    fn matrix(&self) -> Mat4 {
+        match *self {
+            TransformDesc::Translate(offset) => Mat4::translation(offset.into()),
+            TransformDesc::Scale(factors) => Mat4::scale(factors.into()),
+            TransformDesc::RotateX(angle) => Mat4::rotation_x(degrees_to_radians(angle)),
+            TransformDesc::RotateY(angle) => Mat4::rotation_y(degrees_to_radians(angle)),
+            TransformDesc::RotateZ(angle) => Mat4::rotation_z(degrees_to_radians(angle)),
+            TransformDesc::Rotate { axis, angle } => {
+                Mat4::rotation(axis.into(), degrees_to_radians(angle))
+            }
+        }
+    }
+}
+
+impl ObjectDesc {
+    // material of the shapes that know how to sample themselves
+    fn light_material(&self) -> Option<&str> {
+        match self {
+            ObjectDesc::Sphere { material, .. }
+            | ObjectDesc::MovingSphere { material, .. }
+            | ObjectDesc::Triangle { material, .. }
+            | ObjectDesc::Quad { material, .. }
+            | ObjectDesc::Disk { material, .. } => Some(material),
+            _ => None,
+        }
+    }
+}
+
+// loaded obj files are keyed by path and fallback material
+type InstanceKey = (String, Option<String>);
+
+struct Loader<'a> {
+    path: &'a Path,
+    source: &'a str,
+    instances: RefCell<HashMap<InstanceKey, Arc<dyn Hittable>>>,
+}
+
+impl Loader<'_> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> SceneError {
+        let line = self.source[..offset.min(self.source.len())]
+            .matches('\n')
+            .count()
+            + 1;
+
+        SceneError::Invalid {
+            path: self.path.to_path_buf(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    fn camera(&self, desc: CameraDesc, image_size: (u32, u32)) -> Camera {
+        let look_from = Vec3::from(desc.look_from);
+        let look_at = Vec3::from(desc.look_at);
+
+        Camera::new(
+            look_from,
+            look_at,
+            Vec3::from(desc.vup),
+            degrees_to_radians(desc.vfov),
+            degrees_to_radians(desc.defocus_angle),
+            desc.focus_dist
+                .unwrap_or_else(|| (look_from - look_at).length()),
+            image_size,
+        )
+    }
+
+    fn background(&self, desc: Option<Spanned<BackgroundDesc>>) -> Result<Background, SceneError> {
+        let Some(desc) = desc else {
+            return Ok(Background::sky());
+        };
+        let offset = desc.span().start;
+
+        Ok(match desc.into_inner() {
+            BackgroundDesc::Solid { color } => Background::Solid(color.into()),
+            BackgroundDesc::Gradient { bottom, top } => Background::Gradient {
+                bottom: bottom.into(),
+                top: top.into(),
+            },
+            BackgroundDesc::Sky => Background::sky(),
+            BackgroundDesc::Environment { path } => {
+                let path = self.resolve(&path);
+                let map = EnvironmentMap::load(&path).map_err(|err| {
+                    self.error(
+                        offset,
+                        format!("failed to load environment `{}`: {}", path.display(), err),
+                    )
+                })?;
+
+                Background::Environment(map)
+            }
+        })
+    }
+
+    // paths inside a scene are relative to the scene file itself
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.path
+            .parent()
+            .map_or_else(|| PathBuf::from(path), |dir| dir.join(path))
+    }
+
+    fn texture(&self, desc: TextureDesc, offset: usize) -> Result<Box<dyn Texture>, SceneError> {
+        Ok(match desc {
+            TextureDesc::Solid { albedo } => Box::new(Solid::new(albedo.into())),
+            TextureDesc::Checkered { scale, even, odd } => {
+                let texture = Checkered::new(
+                    scale,
+                    self.texture(*even, offset)?,
+                    self.texture(*odd, offset)?,
+                )
+                .map_err(|err| self.error(offset, err.to_string()))?;
+
+                Box::new(texture)
+            }
+            TextureDesc::Noise {
+                seed,
+                scale,
+                low,
+                high,
+            } => Box::new(Noise::new(seed, scale, low.into(), high.into())),
+            TextureDesc::Turbulence {
+                seed,
+                scale,
+                low,
+                high,
+            } => Box::new(Turbulence::new(seed, scale, low.into(), high.into())),
+            TextureDesc::Marble {
+                seed,
+                scale,
+                base,
+                vein,
+            } => Box::new(Marble::new(seed, scale, base.into(), vein.into())),
+            TextureDesc::Wood {
+                seed,
+                scale,
+                light,
+                dark,
+            } => Box::new(Wood::new(seed, scale, light.into(), dark.into())),
+            TextureDesc::Image { path, wrap } => {
+                let path = self.resolve(&path);
+                let texture = ImageTexture::load(&path).map_err(|err| {
+                    self.error(
+                        offset,
+                        format!("failed to load texture `{}`: {}", path.display(), err),
+                    )
+                })?;
+
+                Box::new(texture.with_wrap(match wrap {
+                    WrapDesc::Repeat => Wrap::Repeat,
+                    WrapDesc::Clamp => Wrap::Clamp,
+                }))
+            }
+        })
+    }
+
+    // exactly one of a plain color or a texture has to be given
+    fn color_or_texture(
+        &self,
+        what: &str,
+        color: Option<[f32; 3]>,
+        texture: Option<TextureDesc>,
+        offset: usize,
+    ) -> Result<Box<dyn Texture>, SceneError> {
+        match (color, texture) {
+            (Some(color), None) => Ok(Box::new(Solid::new(color.into()))),
+            (None, Some(texture)) => self.texture(texture, offset),
+            _ => Err(self.error(
+                offset,
+                format!("{what} needs exactly one of a color or `texture`"),
+            )),
+        }
+    }
+
+    fn material(
+        &self,
+        name: &str,
+        desc: Spanned<MaterialDesc>,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        let offset = desc.span().start;
+
+        Ok(match desc.into_inner() {
+            MaterialDesc::Lambertian { albedo, texture } => Arc::new(Lambertian::new(
+                self.color_or_texture(&format!("material `{name}`"), albedo, texture, offset)?,
+            )),
+            MaterialDesc::Metal { albedo, fuzz } => Arc::new(Metal::new(albedo.into(), fuzz)),
+            MaterialDesc::Dielectric { refraction_index } => {
+                Arc::new(Dielectric::new(refraction_index))
+            }
+            MaterialDesc::DiffuseLight { emit, texture } => Arc::new(DiffuseLight::new(
+                self.color_or_texture(&format!("material `{name}`"), emit, texture, offset)?,
+            )),
+        })
+    }
+
+    fn lookup(
+        &self,
+        materials: &HashMap<String, Arc<dyn Material>>,
+        name: &str,
+        offset: usize,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| self.error(offset, format!("unknown material `{name}`")))
+    }
+
+    fn object(
+        &self,
+        materials: &HashMap<String, Arc<dyn Material>>,
+        desc: ObjectDesc,
+        offset: usize,
+        objects: &mut Vec<Box<dyn Hittable>>,
+    ) -> Result<(), SceneError> {
+        let object: Box<dyn Hittable> = match desc {
+            ObjectDesc::Sphere {
+                center,
+                radius,
+                material,
+                seam,
+            } => {
+                if radius <= 0.0 {
+                    return Err(self.error(offset, "sphere radius must be positive"));
+                }
+
+                Box::new(
+                    Sphere::new(
+                        center.into(),
+                        radius,
+                        self.lookup(materials, &material, offset)?,
+                    )
+                    .with_seam(degrees_to_radians(seam)),
+                )
+            }
+            ObjectDesc::MovingSphere {
+                center1,
+                center2,
+                radius,
+                material,
+                seam,
+            } => {
+                if radius <= 0.0 {
+                    return Err(self.error(offset, "sphere radius must be positive"));
+                }
+
+                Box::new(
+                    Sphere::moving(
+                        center1.into(),
+                        center2.into(),
+                        radius,
+                        self.lookup(materials, &material, offset)?,
+                    )
+                    .with_seam(degrees_to_radians(seam)),
+                )
+            }
+            ObjectDesc::Triangle { vertices, material } => {
+                let [p0, p1, p2] = vertices.map(Vec3::from);
+                if is_degenerate(p0, p1, p2) {
+                    return Err(self.error(offset, "triangle vertices must not be collinear"));
+                }
+
+                Box::new(Triangle::new(
+                    p0,
+                    p1,
+                    p2,
+                    self.lookup(materials, &material, offset)?,
+                ))
+            }
+            ObjectDesc::Mesh {
+                positions,
+                normals,
+                uvs,
+                indices,
+                material,
+            } => {
+                if indices.is_empty() {
+                    return Err(self.error(offset, "mesh does not contain any triangles"));
+                }
+
+                let mesh = Mesh::new(
+                    positions.into_iter().map(Vec3::from).collect(),
+                    normals.map(|n| n.into_iter().map(Vec3::from).collect()),
+                    uvs.map(|uv| uv.into_iter().map(|[u, v]| (u, v)).collect()),
+                    indices,
+                    self.lookup(materials, &material, offset)?,
+                )
+                .map_err(|err| self.error(offset, err.to_string()))?;
+
+                Box::new(mesh)
+            }
+            ObjectDesc::Quad {
+                origin,
+                u,
+                v,
+                material,
+            } => {
+                if Vec3::from(u).cross(v.into()).length_squared() == 0.0 {
+                    return Err(self.error(offset, "quad edges must not be parallel"));
+                }
+
+                Box::new(Quad::new(
+                    origin.into(),
+                    u.into(),
+                    v.into(),
+                    self.lookup(materials, &material, offset)?,
+                ))
+            }
+            ObjectDesc::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                if radius <= 0.0 {
+                    return Err(self.error(offset, "disk radius must be positive"));
+                }
+                if Vec3::from(normal).length_squared() == 0.0 {
+                    return Err(self.error(offset, "disk normal must not be zero"));
+                }
+
+                Box::new(Disk::new(
+                    center.into(),
+                    normal.into(),
+                    radius,
+                    self.lookup(materials, &material, offset)?,
+                ))
+            }
+            ObjectDesc::Plane {
+                point,
+                normal,
+                material,
+            } => {
+                if Vec3::from(normal).length_squared() == 0.0 {
+                    return Err(self.error(offset, "plane normal must not be zero"));
+                }
+
+                Box::new(Plane::new(
+                    point.into(),
+                    normal.into(),
+                    self.lookup(materials, &material, offset)?,
+                ))
+            }
+            ObjectDesc::Box { min, max, material } => Box::new(make_box(
+                min.into(),
+                max.into(),
+                self.lookup(materials, &material, offset)?,
+            )),
+            ObjectDesc::Obj { path, material } => {
+                // faces without an mtl material fall back to the given one
+                let default_material = match material {
+                    Some(name) => self.lookup(materials, &name, offset)?,
+                    None => Arc::new(Lambertian::solid(vec3(0.8, 0.8, 0.8))),
+                };
+
+                let meshes = obj::load(self.resolve(&path), default_material)
+                    .map_err(|err| self.error(offset, err.to_string()))?;
+                objects.extend(meshes);
+
+                return Ok(());
+            }
+            ObjectDesc::ConstantMedium {
+                boundary,
+                density,
+                albedo,
+                texture,
+            } => {
+                let mut objects = Vec::new();
+                self.object(materials, *boundary, offset, &mut objects)?;
+
+                let boundary: Box<dyn Hittable> = if objects.len() == 1 {
+                    objects.pop().unwrap()
+                } else {
+                    Box::new(BVHNode::new(&mut objects))
+                };
+
+                let medium = ConstantMedium::new(
+                    boundary,
+                    density,
+                    self.color_or_texture("constant medium", albedo, texture, offset)?,
+                )
+                .map_err(|err| self.error(offset, err.to_string()))?;
+
+                Box::new(medium)
+            }
+            ObjectDesc::Instance { object, transform } => {
+                let mut matrix = Mat4::IDENTITY;
+                for step in transform {
+                    matrix = step.matrix() * matrix;
+                }
+
+                let instance = Transformed::new(self.shared(materials, *object, offset)?, matrix)
+                    .map_err(|err| self.error(offset, err.to_string()))?;
+
+                Box::new(instance)
+            }
+        };
+
+        objects.push(object);
+        Ok(())
+    }
+
+    // instanced objects are built once and shared, so repeated obj files only take memory once
+    fn shared(
+        &self,
+        materials: &HashMap<String, Arc<dyn Material>>,
+        desc: ObjectDesc,
+        offset: usize,
+    ) -> Result<Arc<dyn Hittable>, SceneError> {
+        let key = match &desc {
+            ObjectDesc::Obj { path, material } => Some((path.clone(), material.clone())),
+            _ => None,
+        };
+
+        if let Some(object) = key
+            .as_ref()
+            .and_then(|k| self.instances.borrow().get(k).cloned())
+        {
+            return Ok(object);
+        }
+
+        let mut objects = Vec::new();
+        self.object(materials, desc, offset, &mut objects)?;
+
+        let object: Arc<dyn Hittable> = if objects.len() == 1 {
+            objects.pop().unwrap().into()
+        } else {
+            Arc::new(BVHNode::new(&mut objects))
+        };
+
+        if let Some(key) = key {
+            self.instances.borrow_mut().insert(key, Arc::clone(&object));
+        }
+
+        Ok(object)
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P, image_size: (u32, u32)) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let source =
+        std::fs::read_to_string(path).map_err(|err| SceneError::Io(path.to_path_buf(), err))?;
+
+    let scene: SceneFile =
+        toml::from_str(&source).map_err(|err| SceneError::Parse(path.to_path_buf(), err))?;
+
+    let loader = Loader {
+        path,
+        source: &source,
+        instances: RefCell::new(HashMap::new()),
+    };
+
+    let camera = loader.camera(scene.camera, image_size);
+    let background = loader.background(scene.background)?;
+
+    let mut materials = HashMap::new();
+    let mut emissive = HashSet::new();
+    for (name, desc) in scene.materials {
+        if matches!(desc.get_ref(), MaterialDesc::DiffuseLight { .. }) {
+            emissive.insert(name.clone());
+        }
+
+        let material = loader.material(&name, desc)?;
+        materials.insert(name, material);
+    }
+
+    let mut objects = Vec::new();
+    let mut lights = HittableList::new(Vec::new());
+    for desc in scene.objects {
+        let offset = desc.span().start;
+        let desc = desc.into_inner();
+
+        // top level shapes with an emissive material are sampled directly as lights
+        let is_light = desc.light_material().is_some_and(|m| emissive.contains(m));
+        loader.object(&materials, desc, offset, &mut objects)?;
+
+        if is_light {
+            if let Some(object) = objects.pop() {
+                let light: Arc<dyn Hittable> = object.into();
+                objects.push(Box::new(Arc::clone(&light)));
+                lights.add(Box::new(light));
+            }
+        }
+    }
+
+    Ok(Scene {
+        world: HittableList::new(objects),
+        lights,
+        camera,
+        background,
+    })
+}
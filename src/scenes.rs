@@ -1,21 +1,35 @@
 use std::sync::Arc;
 
-use crate::bvh::BVHNode;
+use crate::background::Background;
 use crate::camera::Camera;
+use crate::error::Error;
 use crate::hittable::{Hittable, HittableList};
-use crate::material::{Dielectric, Lambertian, Material, Metal};
-use crate::math::{degrees_to_radians, random, random_rng, random_vec3, vec3, Vec3};
-use crate::objects::Sphere;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::math::{degrees_to_radians, vec3, Mat4, Rng, Vec3};
+use crate::objects::{make_box, ConstantMedium, Quad, Sphere, Transformed};
+use crate::texture::{Marble, Wood};
 
-pub fn bouncing_spheres(image_width: u32, image_height: u32) -> (BVHNode, Camera) {
-    let mut world: Vec<Box<dyn Hittable>> = Vec::new();
+pub struct Scene {
+    pub world: HittableList,
+    // emitters sampled directly by the renderer, they are part of the world as well
+    pub lights: HittableList,
+    pub camera: Camera,
+    pub background: Background,
+}
+
+pub fn bouncing_spheres(
+    image_width: u32,
+    image_height: u32,
+    rng: &mut Rng,
+) -> Result<Scene, Error> {
+    let mut world = HittableList::new(Vec::new());
 
     let material_ground = Arc::new(Lambertian::checkered(
         0.32,
         vec3(0.2, 0.3, 0.1),
         vec3(0.9, 0.9, 0.9),
-    ));
-    world.push(Box::new(Sphere::new(
+    )?);
+    world.add(Box::new(Sphere::new(
         vec3(0.0, -1000.0, 0.0),
         1000.0,
         material_ground,
@@ -23,40 +37,42 @@ pub fn bouncing_spheres(image_width: u32, image_height: u32) -> (BVHNode, Camera
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = random();
-            let center = vec3(a as f32 + 0.9 * random(), 0.2, b as f32 + 0.9 * random());
+            let choose_mat = rng.random();
+            let center = vec3(
+                a as f32 + 0.9 * rng.random(),
+                0.2,
+                b as f32 + 0.9 * rng.random(),
+            );
 
             if (center - vec3(4.0, 0.2, 0.0)).length() > 0.9 {
                 if choose_mat < 0.8 {
-                    let albedo = random_vec3(0.0, 1.0) * random_vec3(0.0, 1.0);
+                    let albedo = rng.random_vec3(0.0, 1.0) * rng.random_vec3(0.0, 1.0);
                     let material = Arc::new(Lambertian::solid(albedo));
 
-                    let center2 = center + vec3(0.0, random_rng(0.0, 0.5), 0.0);
-                    world.push(Box::new(Sphere::moving(center, center2, 0.2, material)));
+                    let center2 = center + vec3(0.0, rng.random_range(0.0, 0.5), 0.0);
+                    world.add(Box::new(Sphere::moving(center, center2, 0.2, material)));
                 } else if choose_mat < 0.95 {
-                    let albedo = random_vec3(0.5, 1.0);
-                    let fuzz = random_rng(0.0, 0.5);
+                    let albedo = rng.random_vec3(0.5, 1.0);
+                    let fuzz = rng.random_range(0.0, 0.5);
 
                     let material = Arc::new(Metal::new(albedo, fuzz));
-                    world.push(Box::new(Sphere::new(center, 0.2, material)));
+                    world.add(Box::new(Sphere::new(center, 0.2, material)));
                 } else {
                     let material = Arc::new(Dielectric::new(1.5));
-                    world.push(Box::new(Sphere::new(center, 0.2, material)));
+                    world.add(Box::new(Sphere::new(center, 0.2, material)));
                 }
             }
         }
     }
 
     let material1 = Arc::new(Dielectric::new(1.5));
-    world.push(Box::new(Sphere::new(vec3(0.0, 1.0, 0.0), 1.0, material1)));
+    world.add(Box::new(Sphere::new(vec3(0.0, 1.0, 0.0), 1.0, material1)));
 
     let material2 = Arc::new(Lambertian::solid(vec3(0.4, 0.2, 0.1)));
-    world.push(Box::new(Sphere::new(vec3(-4.0, 1.0, 0.0), 1.0, material2)));
+    world.add(Box::new(Sphere::new(vec3(-4.0, 1.0, 0.0), 1.0, material2)));
 
     let material3 = Arc::new(Metal::new(vec3(0.7, 0.6, 0.5), 0.0));
-    world.push(Box::new(Sphere::new(vec3(4.0, 1.0, 0.0), 1.0, material3)));
-
-    let world = BVHNode::new(&mut world);
+    world.add(Box::new(Sphere::new(vec3(4.0, 1.0, 0.0), 1.0, material3)));
 
     let camera = Camera::new(
         vec3(13.0, 2.0, 3.0),
@@ -68,17 +84,22 @@ pub fn bouncing_spheres(image_width: u32, image_height: u32) -> (BVHNode, Camera
         (image_width, image_height),
     );
 
-    (world, camera)
+    Ok(Scene {
+        world,
+        lights: HittableList::new(Vec::new()),
+        camera,
+        background: Background::sky(),
+    })
 }
 
-pub fn checkered_spheres(image_width: u32, image_height: u32) -> (HittableList, Camera) {
+pub fn checkered_spheres(image_width: u32, image_height: u32) -> Result<Scene, Error> {
     let mut world = HittableList::new(Vec::new());
 
     let material: Arc<dyn Material> = Arc::new(Lambertian::checkered(
         0.32,
         vec3(0.2, 0.3, 0.1),
         vec3(0.9, 0.9, 0.9),
-    ));
+    )?);
 
     world.add(Box::new(Sphere::new(
         vec3(0.0, -10.0, 0.0),
@@ -101,5 +122,231 @@ pub fn checkered_spheres(image_width: u32, image_height: u32) -> (HittableList,
         (image_width, image_height),
     );
 
-    (world, camera)
+    Ok(Scene {
+        world,
+        lights: HittableList::new(Vec::new()),
+        camera,
+        background: Background::sky(),
+    })
+}
+
+pub fn simple_light(image_width: u32, image_height: u32) -> Result<Scene, Error> {
+    let mut world = HittableList::new(Vec::new());
+
+    let ground = Arc::new(Lambertian::checkered(
+        0.32,
+        vec3(0.2, 0.3, 0.1),
+        vec3(0.9, 0.9, 0.9),
+    )?);
+    world.add(Box::new(Sphere::new(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        ground,
+    )));
+    world.add(Box::new(Sphere::new(
+        vec3(0.0, 2.0, 0.0),
+        2.0,
+        Arc::new(Lambertian::solid(vec3(0.4, 0.2, 0.1))),
+    )));
+
+    let mut lights = HittableList::new(Vec::new());
+    let light = Arc::new(Sphere::new(
+        vec3(0.0, 7.0, 0.0),
+        2.0,
+        Arc::new(DiffuseLight::solid(vec3(4.0, 4.0, 4.0))),
+    ));
+    world.add(Box::new(Arc::clone(&light)));
+    lights.add(Box::new(light));
+
+    let camera = Camera::new(
+        vec3(26.0, 3.0, 6.0),
+        vec3(0.0, 2.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        degrees_to_radians(20.0),
+        degrees_to_radians(0.0),
+        1.0,
+        (image_width, image_height),
+    );
+
+    Ok(Scene {
+        world,
+        lights,
+        camera,
+        background: Background::Solid(Vec3::ZEROS),
+    })
+}
+
+pub fn perlin_spheres(image_width: u32, image_height: u32) -> Result<Scene, Error> {
+    let mut world = HittableList::new(Vec::new());
+
+    let marble = Arc::new(Lambertian::new(Box::new(Marble::new(
+        0,
+        4.0,
+        vec3(0.9, 0.9, 0.9),
+        vec3(0.2, 0.2, 0.25),
+    ))));
+    world.add(Box::new(Sphere::new(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        marble,
+    )));
+
+    let wood = Arc::new(Lambertian::new(Box::new(Wood::new(
+        1,
+        4.0,
+        vec3(0.75, 0.55, 0.3),
+        vec3(0.35, 0.2, 0.08),
+    ))));
+    world.add(Box::new(Sphere::new(vec3(0.0, 2.0, 0.0), 2.0, wood)));
+
+    let camera = Camera::new(
+        vec3(13.0, 2.0, 3.0),
+        Vec3::ZEROS,
+        vec3(0.0, 1.0, 0.0),
+        degrees_to_radians(20.0),
+        degrees_to_radians(0.0),
+        1.0,
+        (image_width, image_height),
+    );
+
+    Ok(Scene {
+        world,
+        lights: HittableList::new(Vec::new()),
+        camera,
+        background: Background::sky(),
+    })
+}
+
+// the five walls of the cornell box plus the given ceiling light, which is also returned as the only light
+fn cornell_room(light: Quad) -> (HittableList, HittableList) {
+    let mut world = HittableList::new(Vec::new());
+    let mut lights = HittableList::new(Vec::new());
+
+    let red: Arc<dyn Material> = Arc::new(Lambertian::solid(vec3(0.65, 0.05, 0.05)));
+    let white: Arc<dyn Material> = Arc::new(Lambertian::solid(vec3(0.73, 0.73, 0.73)));
+    let green: Arc<dyn Material> = Arc::new(Lambertian::solid(vec3(0.12, 0.45, 0.15)));
+
+    world.add(Box::new(Quad::new(
+        vec3(555.0, 0.0, 0.0),
+        vec3(0.0, 555.0, 0.0),
+        vec3(0.0, 0.0, 555.0),
+        green,
+    )));
+    world.add(Box::new(Quad::new(
+        vec3(0.0, 0.0, 0.0),
+        vec3(0.0, 555.0, 0.0),
+        vec3(0.0, 0.0, 555.0),
+        red,
+    )));
+    let light = Arc::new(light);
+    world.add(Box::new(Arc::clone(&light)));
+    lights.add(Box::new(light));
+    world.add(Box::new(Quad::new(
+        vec3(0.0, 0.0, 0.0),
+        vec3(555.0, 0.0, 0.0),
+        vec3(0.0, 0.0, 555.0),
+        Arc::clone(&white),
+    )));
+    world.add(Box::new(Quad::new(
+        vec3(555.0, 555.0, 555.0),
+        vec3(-555.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -555.0),
+        Arc::clone(&white),
+    )));
+    world.add(Box::new(Quad::new(
+        vec3(0.0, 0.0, 555.0),
+        vec3(555.0, 0.0, 0.0),
+        vec3(0.0, 555.0, 0.0),
+        white,
+    )));
+
+    (world, lights)
+}
+
+fn cornell_camera(image_width: u32, image_height: u32) -> Camera {
+    Camera::new(
+        vec3(278.0, 278.0, -800.0),
+        vec3(278.0, 278.0, 0.0),
+        vec3(0.0, 1.0, 0.0),
+        degrees_to_radians(40.0),
+        degrees_to_radians(0.0),
+        1.0,
+        (image_width, image_height),
+    )
+}
+
+pub fn cornell_box(image_width: u32, image_height: u32) -> Result<Scene, Error> {
+    let light = Arc::new(DiffuseLight::solid(vec3(15.0, 15.0, 15.0)));
+    let (mut world, lights) = cornell_room(Quad::new(
+        vec3(343.0, 554.0, 332.0),
+        vec3(-130.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -105.0),
+        light,
+    ));
+
+    let white: Arc<dyn Material> = Arc::new(Lambertian::solid(vec3(0.73, 0.73, 0.73)));
+
+    let box1 = make_box(Vec3::ZEROS, vec3(165.0, 330.0, 165.0), Arc::clone(&white));
+    world.add(Box::new(Transformed::new(
+        Box::new(box1) as Box<dyn Hittable>,
+        Mat4::translation(vec3(265.0, 0.0, 295.0)) * Mat4::rotation_y(degrees_to_radians(15.0)),
+    )?));
+
+    let box2 = make_box(Vec3::ZEROS, vec3(165.0, 165.0, 165.0), white);
+    world.add(Box::new(Transformed::new(
+        Box::new(box2) as Box<dyn Hittable>,
+        Mat4::translation(vec3(130.0, 0.0, 65.0)) * Mat4::rotation_y(degrees_to_radians(-18.0)),
+    )?));
+
+    let camera = cornell_camera(image_width, image_height);
+
+    Ok(Scene {
+        world,
+        lights,
+        camera,
+        background: Background::Solid(Vec3::ZEROS),
+    })
+}
+
+pub fn cornell_smoke(image_width: u32, image_height: u32) -> Result<Scene, Error> {
+    let light = Arc::new(DiffuseLight::solid(vec3(7.0, 7.0, 7.0)));
+    let (mut world, lights) = cornell_room(Quad::new(
+        vec3(113.0, 554.0, 127.0),
+        vec3(330.0, 0.0, 0.0),
+        vec3(0.0, 0.0, 305.0),
+        light,
+    ));
+
+    let white: Arc<dyn Material> = Arc::new(Lambertian::solid(vec3(0.73, 0.73, 0.73)));
+
+    let box1 = make_box(Vec3::ZEROS, vec3(165.0, 330.0, 165.0), Arc::clone(&white));
+    let box1 = Transformed::new(
+        Box::new(box1) as Box<dyn Hittable>,
+        Mat4::translation(vec3(265.0, 0.0, 295.0)) * Mat4::rotation_y(degrees_to_radians(15.0)),
+    )?;
+    world.add(Box::new(ConstantMedium::solid(
+        Box::new(box1),
+        0.01,
+        Vec3::ZEROS,
+    )?));
+
+    let box2 = make_box(Vec3::ZEROS, vec3(165.0, 165.0, 165.0), white);
+    let box2 = Transformed::new(
+        Box::new(box2) as Box<dyn Hittable>,
+        Mat4::translation(vec3(130.0, 0.0, 65.0)) * Mat4::rotation_y(degrees_to_radians(-18.0)),
+    )?;
+    world.add(Box::new(ConstantMedium::solid(
+        Box::new(box2),
+        0.01,
+        Vec3::ONES,
+    )?));
+
+    let camera = cornell_camera(image_width, image_height);
+
+    Ok(Scene {
+        world,
+        lights,
+        camera,
+        background: Background::Solid(Vec3::ZEROS),
+    })
 }
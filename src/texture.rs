@@ -1,6 +1,9 @@
-use crate::math::Vec3;
+use std::path::Path;
 
-pub trait Texture {
+use crate::error::Error;
+use crate::math::{srgb_to_linear, vec3, Perlin, Vec3};
+
+pub trait Texture: Send + Sync {
     fn value(&self, uv: (f32, f32), point: &Vec3) -> Vec3;
 }
 
@@ -27,12 +30,18 @@ pub struct Checkered {
 }
 
 impl Checkered {
-    pub fn new(scale: f32, even: Box<dyn Texture>, odd: Box<dyn Texture>) -> Self {
-        Self {
+    pub fn new(scale: f32, even: Box<dyn Texture>, odd: Box<dyn Texture>) -> Result<Self, Error> {
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(Error::InvalidInput(format!(
+                "checkered texture scale must be positive and finite, got {scale}"
+            )));
+        }
+
+        Ok(Self {
             inv_scale: 1.0 / scale,
             even,
             odd,
-        }
+        })
     }
 }
 
@@ -49,3 +58,211 @@ impl Texture for Checkered {
         }
     }
 }
+
+const TURBULENCE_DEPTH: u32 = 7;
+
+fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    a * (1.0 - t) + b * t
+}
+
+pub struct Noise {
+    perlin: Perlin,
+    scale: f32,
+    low: Vec3,
+    high: Vec3,
+}
+
+impl Noise {
+    pub fn new(seed: u64, scale: f32, low: Vec3, high: Vec3) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            low,
+            high,
+        }
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _uv: (f32, f32), point: &Vec3) -> Vec3 {
+        let t = 0.5 * (1.0 + self.perlin.noise(&(*point * self.scale)));
+        lerp(self.low, self.high, t.clamp(0.0, 1.0))
+    }
+}
+
+pub struct Turbulence {
+    perlin: Perlin,
+    scale: f32,
+    low: Vec3,
+    high: Vec3,
+}
+
+impl Turbulence {
+    pub fn new(seed: u64, scale: f32, low: Vec3, high: Vec3) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            low,
+            high,
+        }
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, _uv: (f32, f32), point: &Vec3) -> Vec3 {
+        let t = self
+            .perlin
+            .turbulence(&(*point * self.scale), TURBULENCE_DEPTH);
+        lerp(self.low, self.high, t.clamp(0.0, 1.0))
+    }
+}
+
+pub struct Marble {
+    perlin: Perlin,
+    scale: f32,
+    base: Vec3,
+    vein: Vec3,
+}
+
+impl Marble {
+    pub fn new(seed: u64, scale: f32, base: Vec3, vein: Vec3) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            base,
+            vein,
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _uv: (f32, f32), point: &Vec3) -> Vec3 {
+        // turbulence distorts regular stripes along z into veins
+        let turbulence = self.perlin.turbulence(point, TURBULENCE_DEPTH);
+        let t = 0.5 * (1.0 + (self.scale * point.z + 10.0 * turbulence).sin());
+
+        lerp(self.vein, self.base, t)
+    }
+}
+
+pub struct Wood {
+    perlin: Perlin,
+    scale: f32,
+    light: Vec3,
+    dark: Vec3,
+}
+
+impl Wood {
+    pub fn new(seed: u64, scale: f32, light: Vec3, dark: Vec3) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            scale,
+            light,
+            dark,
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _uv: (f32, f32), point: &Vec3) -> Vec3 {
+        // growth rings are concentric around the y axis, warped a little by noise
+        let p = *point * self.scale;
+        let radius = (p.x * p.x + p.z * p.z).sqrt();
+        let rings = radius + 0.4 * self.perlin.noise(&(p * 0.5));
+
+        let t = rings - rings.floor();
+        lerp(self.light, self.dark, t * t)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Wrap {
+    Repeat,
+    Clamp,
+}
+
+impl Wrap {
+    // maps a texel coordinate into 0..size
+    fn apply(self, coord: i64, size: u32) -> u32 {
+        match self {
+            Wrap::Repeat => coord.rem_euclid(size as i64) as u32,
+            Wrap::Clamp => coord.clamp(0, size as i64 - 1) as u32,
+        }
+    }
+}
+
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+    wrap: Wrap,
+}
+
+impl ImageTexture {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?;
+
+        // float formats already hold linear values, everything else is assumed to be srgb
+        let linear = matches!(
+            image,
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+        );
+
+        let image = image.into_rgb32f();
+        let (width, height) = image.dimensions();
+
+        let pixels = image
+            .pixels()
+            .map(|p| {
+                if linear {
+                    vec3(p[0], p[1], p[2])
+                } else {
+                    vec3(
+                        srgb_to_linear(p[0]),
+                        srgb_to_linear(p[1]),
+                        srgb_to_linear(p[2]),
+                    )
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            wrap: Wrap::Repeat,
+        })
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, uv: (f32, f32), _point: &Vec3) -> Vec3 {
+        // image rows go top to bottom while v goes up, texel centers sit at half offsets
+        let x = uv.0 * self.width as f32 - 0.5;
+        let y = (1.0 - uv.1) * self.height as f32 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x0 + 1, y0) * tx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - tx) + self.texel(x0 + 1, y0 + 1) * tx;
+
+        top * (1.0 - ty) + bottom * ty
+    }
+}
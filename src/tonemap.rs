@@ -0,0 +1,114 @@
+use crate::math::{luminance, vec3, Vec3};
+
+// curve compressing linear radiance into the displayable [0, 1] range
+#[derive(Clone, Copy, Default)]
+pub enum ToneMap {
+    // values above one are clipped
+    #[default]
+    Clamp,
+    // luminance based reinhard, c / (1 + l)
+    Reinhard,
+    // stephen hill's fit of the aces reference rendering and output transforms
+    Aces,
+    // troy sobotka's agx with the default look, approximated by a polynomial
+    Agx,
+}
+
+impl ToneMap {
+    pub fn apply(self, color: Vec3) -> Vec3 {
+        match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => color / (1.0 + luminance(color).max(0.0)),
+            ToneMap::Aces => aces(color),
+            ToneMap::Agx => agx(color),
+        }
+    }
+}
+
+// how the linear framebuffer is turned into display values for 8-bit output
+#[derive(Clone, Copy, Default)]
+pub struct DisplayTransform {
+    // in stops, each one doubles the brightness
+    pub exposure: f32,
+    pub tone_map: ToneMap,
+}
+
+impl DisplayTransform {
+    // linear display values, still to be encoded with the srgb transfer function
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let mapped = self.tone_map.apply(color * self.exposure.exp2());
+        vec3(
+            mapped.x.clamp(0.0, 1.0),
+            mapped.y.clamp(0.0, 1.0),
+            mapped.z.clamp(0.0, 1.0),
+        )
+    }
+}
+
+type Mat3 = [[f32; 3]; 3];
+
+fn mul(m: &Mat3, v: Vec3) -> Vec3 {
+    vec3(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+    )
+}
+
+// linear srgb to the aces working space with the rrt saturation folded in
+const ACES_INPUT: Mat3 = [
+    [0.59719, 0.35458, 0.04823],
+    [0.07600, 0.90834, 0.01566],
+    [0.02840, 0.13383, 0.83777],
+];
+
+// back from the odt output to linear srgb
+const ACES_OUTPUT: Mat3 = [
+    [1.60475, -0.53108, -0.07367],
+    [-0.10208, 1.10813, -0.00605],
+    [-0.00327, -0.07276, 1.07602],
+];
+
+fn aces(color: Vec3) -> Vec3 {
+    let curve =
+        |v: f32| (v * (v + 0.0245786) - 0.000090537) / (v * (0.983729 * v + 0.432951) + 0.238081);
+
+    let v = mul(&ACES_INPUT, color);
+    mul(&ACES_OUTPUT, vec3(curve(v.x), curve(v.y), curve(v.z)))
+}
+
+// insets the primaries so bright saturated colors bleach towards white
+const AGX_INSET: Mat3 = [
+    [0.84247906, 0.0784336, 0.079223745],
+    [0.042328242, 0.87846864, 0.07916613],
+    [0.042375655, 0.0784336, 0.879143],
+];
+
+const AGX_OUTSET: Mat3 = [
+    [1.196879, -0.09802088, -0.09902974],
+    [-0.052896852, 1.1519031, -0.098961177],
+    [-0.052971636, -0.09804345, 1.1510737],
+];
+
+// exposure range in stops around middle grey that the agx curve covers
+const AGX_MIN_EV: f32 = -12.47393;
+const AGX_MAX_EV: f32 = 4.026069;
+
+fn agx(color: Vec3) -> Vec3 {
+    let curve = |v: f32| {
+        let x = ((v.max(1e-10).log2().clamp(AGX_MIN_EV, AGX_MAX_EV)) - AGX_MIN_EV)
+            / (AGX_MAX_EV - AGX_MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+
+    let v = mul(&AGX_INSET, color);
+    let v = mul(&AGX_OUTSET, vec3(curve(v.x), curve(v.y), curve(v.z)));
+
+    // the curve outputs display encoded values, brought back to linear for the srgb encoding
+    let linear = |c: f32| c.max(0.0).powf(2.2);
+    vec3(linear(v.x), linear(v.y), linear(v.z))
+}